@@ -0,0 +1,35 @@
+//! Hover the square with the mouse, then press space to toggle a press on it.
+use bevy::prelude::*;
+use bevy_picking_state_machine::{
+    DebugPressKey, EntityPickingState, PickingStateMachine, PickingStateMachinePlugin,
+};
+
+#[derive(Component)]
+struct Square;
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, PickingStateMachinePlugin::default()))
+        .insert_resource(DebugPressKey(KeyCode::Space))
+        .add_systems(Startup, spawn)
+        .add_systems(Update, color)
+        .run();
+}
+
+fn spawn(mut commands: Commands) {
+    commands.spawn(Camera2d);
+    commands.spawn((Square, Sprite::from_color(Color::WHITE, Vec2::splat(200.0))));
+}
+
+fn color(
+    state_machine: Res<PickingStateMachine>,
+    mut squares: Query<(Entity, &mut Sprite), With<Square>>,
+) {
+    for (entity, mut sprite) in &mut squares {
+        sprite.color = match state_machine.get_state(entity) {
+            EntityPickingState::None => Color::WHITE,
+            EntityPickingState::Hover => Color::srgb(1.0, 1.0, 0.0),
+            EntityPickingState::Pressed => Color::srgb(1.0, 0.0, 0.0),
+        };
+    }
+}
@@ -0,0 +1,77 @@
+//! Scripts a press, drag and release with `PickingInputOverride` and injected hits,
+//! then prints the transitions of each frame.
+use bevy::{
+    input::{InputPlugin, mouse::MouseButton},
+    picking::{
+        PickingSystems,
+        backend::{HitData, PointerHits},
+        pointer::PointerId,
+    },
+    prelude::*,
+};
+use bevy_picking_state_machine::{
+    PickingInputOverride, PickingStateMachine, PickingStateMachinePlugin,
+};
+
+#[derive(Component)]
+struct Target;
+
+#[derive(Component)]
+struct FakeCamera;
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        InputPlugin,
+        PickingStateMachinePlugin::builder()
+            .drag_threshold(4.0)
+            .build(),
+    ));
+    app.add_message::<PointerHits>();
+    app.init_resource::<PickingInputOverride>();
+    app.add_systems(Startup, spawn);
+    app.add_systems(PreUpdate, script.before(PickingSystems::Hover));
+    app.add_systems(Update, print_transitions);
+    for _ in 0..6 {
+        app.update();
+    }
+}
+
+fn spawn(mut commands: Commands) {
+    commands.spawn(FakeCamera);
+    commands.spawn(Target);
+}
+
+fn script(
+    mut frame: Local<usize>,
+    mut input: ResMut<PickingInputOverride>,
+    mut hits: MessageWriter<PointerHits>,
+    target: Single<Entity, With<Target>>,
+    camera: Single<Entity, With<FakeCamera>>,
+) {
+    let (pointer, buttons) = match *frame {
+        0 => (Vec2::new(10.0, 10.0), vec![]),
+        1 => (Vec2::new(10.0, 10.0), vec![MouseButton::Left]),
+        2 => (Vec2::new(40.0, 10.0), vec![MouseButton::Left]),
+        3 => (Vec2::new(80.0, 10.0), vec![MouseButton::Left]),
+        _ => (Vec2::new(80.0, 10.0), vec![]),
+    };
+    *frame += 1;
+    input.pointer = Some(pointer);
+    input.buttons = Some(buttons);
+    // The target covers the whole screen.
+    hits.write(PointerHits::new(
+        PointerId::Mouse,
+        vec![(*target, HitData::new(*camera, 0.0, None, None))],
+        0.0,
+    ));
+}
+
+fn print_transitions(mut frame: Local<usize>, state_machine: Res<PickingStateMachine>) {
+    println!("frame {}: {:?}", *frame, state_machine.current);
+    for transition in state_machine.iter_transitions() {
+        println!("    {transition:?}");
+    }
+    *frame += 1;
+}
@@ -0,0 +1,70 @@
+use bevy::{
+    camera::{Camera, primitives::Aabb},
+    ecs::{
+        entity::Entity,
+        hierarchy::Children,
+        system::{Query, SystemParam},
+    },
+    math::{Rect, Vec3},
+    transform::components::GlobalTransform,
+};
+
+use crate::propagation::PropagatedPickingStateMachine;
+
+/// [`SystemParam`] computing the screen space bounds of the active entity
+/// and entities equivalent to it by [`PickingPropagation`](crate::PickingPropagation),
+/// e.g. to draw one outline around a whole widget.
+///
+/// Bounds are the [`Aabb`] of each entity projected by the camera of the active hit.
+/// Entities without an [`Aabb`] or [`GlobalTransform`] are skipped instead of panicking.
+#[derive(SystemParam)]
+pub struct ActiveGroupBounds<'w, 's> {
+    pub propagated: PropagatedPickingStateMachine<'w, 's>,
+    children: Query<'w, 's, &'static Children>,
+    bounds: Query<'w, 's, (&'static GlobalTransform, &'static Aabb)>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl ActiveGroupBounds<'_, '_> {
+    /// Returns the union of the viewport rects of the active group, in logical pixels.
+    ///
+    /// Returns `None` if nothing is active, the camera is missing,
+    /// or no entity in the group has bounds.
+    pub fn active_group_rect(&self) -> Option<Rect> {
+        let state_machine = &self.propagated.state_machine;
+        let active = state_machine.get_active_entity()?;
+        let (camera, camera_transform) = self.cameras.get(state_machine.current_camera?).ok()?;
+        self.propagated
+            .equivalent_entities(active, &self.children)
+            .into_iter()
+            .filter_map(|entity| self.viewport_rect(entity, camera, camera_transform))
+            .reduce(|a, b| a.union(b))
+    }
+
+    /// Returns the viewport rect of the corners of an entity's [`Aabb`],
+    /// `None` if any corner is outside of the camera's depth range.
+    fn viewport_rect(
+        &self,
+        entity: Entity,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+    ) -> Option<Rect> {
+        let (transform, aabb) = self.bounds.get(entity).ok()?;
+        let (min, max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+        let mut corners = (0..8).map(|i| {
+            let pick = |bit: i32, min: f32, max: f32| if i & bit == 0 { min } else { max };
+            let corner = Vec3::new(
+                pick(1, min.x, max.x),
+                pick(2, min.y, max.y),
+                pick(4, min.z, max.z),
+            );
+            camera
+                .world_to_viewport(camera_transform, transform.transform_point(corner))
+                .ok()
+        });
+        let first = corners.next()??;
+        corners.try_fold(Rect::from_corners(first, first), |rect, point| {
+            Some(rect.union_point(point?))
+        })
+    }
+}
@@ -0,0 +1,220 @@
+use bevy::input::{gamepad::GamepadButton, mouse::MouseButton};
+
+use crate::{
+    HitTiebreak, MultiButtonPolicy, PickingStateMachinePlugin, PointerOrigin, PressRetention,
+};
+
+/// Builder for [`PickingStateMachinePlugin`], starting from its default.
+///
+/// Each setter corresponds to the field of the same name.
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_picking_state_machine::PickingStateMachinePlugin;
+/// let plugin = PickingStateMachinePlugin::builder()
+///     .allowed_button(MouseButton::Right)
+///     .drag_threshold(8.0)
+///     .build();
+/// assert_eq!(plugin.allowed_buttons, [MouseButton::Left, MouseButton::Right]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PickingStateMachinePluginBuilder(PickingStateMachinePlugin);
+
+impl PickingStateMachinePlugin {
+    /// Create a [`PickingStateMachinePluginBuilder`], the recommended way to configure the plugin.
+    pub fn builder() -> PickingStateMachinePluginBuilder {
+        PickingStateMachinePluginBuilder::default()
+    }
+}
+
+impl PickingStateMachinePluginBuilder {
+    /// Add a button to [`PickingStateMachinePlugin::allowed_buttons`],
+    /// which contains [`MouseButton::Left`] by default.
+    pub fn allowed_button(mut self, button: MouseButton) -> Self {
+        if !self.0.allowed_buttons.contains(&button) {
+            self.0.allowed_buttons.push(button);
+        }
+        self
+    }
+
+    /// Replace [`PickingStateMachinePlugin::allowed_buttons`].
+    pub fn allowed_buttons(mut self, buttons: impl IntoIterator<Item = MouseButton>) -> Self {
+        self.0.allowed_buttons = buttons.into_iter().collect();
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::cancel_hover`].
+    pub fn cancel_hover(mut self, value: bool) -> Self {
+        self.0.cancel_hover = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::multi_button_policy`].
+    pub fn multi_button_policy(mut self, value: MultiButtonPolicy) -> Self {
+        self.0.multi_button_policy = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::acquire_while_pressed`].
+    pub fn acquire_while_pressed(mut self, value: bool) -> Self {
+        self.0.acquire_while_pressed = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::press_acquire_on_spawn`].
+    pub fn press_acquire_on_spawn(mut self, value: bool) -> Self {
+        self.0.press_acquire_on_spawn = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::pointer_origin`].
+    pub fn pointer_origin(mut self, value: PointerOrigin) -> Self {
+        self.0.pointer_origin = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::within_batch_tiebreak`].
+    pub fn within_batch_tiebreak(mut self, value: HitTiebreak) -> Self {
+        self.0.within_batch_tiebreak = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::prefer_current_on_tie`].
+    pub fn prefer_current_on_tie(mut self, value: bool) -> Self {
+        self.0.prefer_current_on_tie = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::emit_hit_updates`].
+    pub fn emit_hit_updates(mut self, value: bool) -> Self {
+        self.0.emit_hit_updates = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::prediction_time`].
+    pub fn prediction_time(mut self, value: f32) -> Self {
+        self.0.prediction_time = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::resolve_interval`].
+    pub fn resolve_interval(mut self, value: f32) -> Self {
+        self.0.resolve_interval = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::multi_click_interval`].
+    pub fn multi_click_interval(mut self, value: f32) -> Self {
+        self.0.multi_click_interval = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::multi_click_radius`].
+    pub fn multi_click_radius(mut self, value: f32) -> Self {
+        self.0.multi_click_radius = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::resume_after_chord`].
+    pub fn resume_after_chord(mut self, value: bool) -> Self {
+        self.0.resume_after_chord = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::release_debounce`].
+    pub fn release_debounce(mut self, value: f32) -> Self {
+        self.0.release_debounce = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::release_debounce_radius`].
+    pub fn release_debounce_radius(mut self, value: f32) -> Self {
+        self.0.release_debounce_radius = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::skip_when_quiescent`].
+    pub fn skip_when_quiescent(mut self, value: bool) -> Self {
+        self.0.skip_when_quiescent = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::rehover_on_release`].
+    pub fn rehover_on_release(mut self, value: bool) -> Self {
+        self.0.rehover_on_release = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::emit_events`].
+    pub fn emit_events(mut self, value: bool) -> Self {
+        self.0.emit_events = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::drag_threshold`].
+    pub fn drag_threshold(mut self, value: f32) -> Self {
+        self.0.drag_threshold = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::drop_dwell`].
+    pub fn drop_dwell(mut self, value: f32) -> Self {
+        self.0.drop_dwell = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::max_hit_batches`].
+    pub fn max_hit_batches(mut self, value: Option<usize>) -> Self {
+        self.0.max_hit_batches = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::long_press_duration`].
+    pub fn long_press_duration(mut self, value: Option<f32>) -> Self {
+        self.0.long_press_duration = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::respect_window_focus`].
+    pub fn respect_window_focus(mut self, value: bool) -> Self {
+        self.0.respect_window_focus = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::press_retention`].
+    pub fn press_retention(mut self, value: PressRetention) -> Self {
+        self.0.press_retention = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::despawn_resets`].
+    pub fn despawn_resets(mut self, value: bool) -> Self {
+        self.0.despawn_resets = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::route_scroll`].
+    pub fn route_scroll(mut self, value: bool) -> Self {
+        self.0.route_scroll = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::line_to_pixel`].
+    pub fn line_to_pixel(mut self, value: f32) -> Self {
+        self.0.line_to_pixel = value;
+        self
+    }
+
+    /// Set [`PickingStateMachinePlugin::gamepad_confirm`].
+    pub fn gamepad_confirm(mut self, value: Option<GamepadButton>) -> Self {
+        self.0.gamepad_confirm = value;
+        self
+    }
+
+    /// Returns the configured plugin.
+    pub fn build(self) -> PickingStateMachinePlugin {
+        self.0
+    }
+}
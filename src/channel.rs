@@ -0,0 +1,62 @@
+use std::sync::{Arc, Mutex};
+
+use async_channel::{Receiver, Sender, TrySendError};
+use bevy::ecs::{resource::Resource, system::Res};
+
+use crate::{AllPickingStateMachines, PickingTransition};
+
+/// Optional resource forwarding transitions to bounded channels,
+/// e.g. for async tasks reacting to clicks off the schedule.
+///
+/// Each receiver returned by [`TransitionChannel::subscribe`] receives every transition
+/// sent after it subscribed. When its channel is full, its oldest transition is dropped.
+///
+/// Requires the `channel` feature.
+#[derive(Debug, Clone, Resource)]
+pub struct TransitionChannel {
+    capacity: usize,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+/// Channel of a subscriber, the receiver is kept to drop the oldest transition when full.
+type Subscriber = (Sender<PickingTransition>, Receiver<PickingTransition>);
+
+impl TransitionChannel {
+    /// Create a channel holding at most `capacity` transitions per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        TransitionChannel {
+            capacity: capacity.max(1),
+            subscribers: Default::default(),
+        }
+    }
+
+    /// Returns a receiver of all transitions from now on.
+    pub fn subscribe(&self) -> Receiver<PickingTransition> {
+        let (sender, receiver) = async_channel::bounded(self.capacity);
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|x| x.into_inner());
+        subscribers.push((sender, receiver.clone()));
+        receiver
+    }
+}
+
+pub(crate) fn transition_channel_system(
+    channel: Res<TransitionChannel>,
+    state_machines: AllPickingStateMachines,
+) {
+    let mut subscribers = channel
+        .subscribers
+        .lock()
+        .unwrap_or_else(|x| x.into_inner());
+    // Only the kept receiver is left once the subscriber drops theirs.
+    subscribers.retain(|(_, receiver)| receiver.receiver_count() > 1);
+    for transition in state_machines.iter_transitions() {
+        for (sender, receiver) in subscribers.iter() {
+            let mut transition = transition.clone();
+            // Never closed since a receiver is kept.
+            while let Err(TrySendError::Full(x)) = sender.try_send(transition) {
+                let _ = receiver.try_recv();
+                transition = x;
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Run conditions based on [`PickingStateMachine`].
+
+use bevy::ecs::system::Res;
+
+use crate::PickingStateMachine;
+
+/// Returns true if [`PickingStateMachine::is_quiescent`] is false.
+pub fn picking_not_quiescent(state_machine: Res<PickingStateMachine>) -> bool {
+    !state_machine.is_quiescent()
+}
+
+/// Returns true if any entity is hovered or pressed.
+pub fn any_active(state_machine: Res<PickingStateMachine>) -> bool {
+    state_machine.get_active_entity().is_some()
+}
+
+/// Returns true if [`PickingStateMachine::is_hovering`].
+pub fn is_hovering(state_machine: Res<PickingStateMachine>) -> bool {
+    state_machine.is_hovering()
+}
+
+/// Returns true if [`PickingStateMachine::is_pressing`].
+pub fn is_pressing(state_machine: Res<PickingStateMachine>) -> bool {
+    state_machine.is_pressing()
+}
+
+/// Returns true if any transition is queued this frame.
+pub fn transition_occurred(state_machine: Res<PickingStateMachine>) -> bool {
+    !state_machine.transitions.is_empty()
+}
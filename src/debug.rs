@@ -0,0 +1,70 @@
+use bevy::{
+    app::{Plugin, Update},
+    camera::Camera,
+    color::{
+        Color,
+        palettes::css::{RED, WHITE, YELLOW},
+    },
+    ecs::system::{Query, Res},
+    gizmos::gizmos::Gizmos,
+    math::{Ray3d, Vec2, Vec3},
+    transform::components::GlobalTransform,
+};
+
+use crate::{EntityPickingState, PickingStateMachine};
+
+/// Draws the pointer and a line to the active entity with gizmos.
+///
+/// The color shows the state: white for none, yellow for hover and red for pressed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PickingDebugPlugin;
+
+impl Plugin for PickingDebugPlugin {
+    fn build(&self, app: &mut bevy::app::App) {
+        app.add_systems(Update, picking_debug_system);
+    }
+}
+
+fn picking_debug_system(
+    state_machine: Res<PickingStateMachine>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    transforms: Query<&GlobalTransform>,
+    mut gizmos: Gizmos,
+) {
+    if state_machine.pointer_is_out_of_bounds {
+        return;
+    }
+    let Some((camera, camera_transform)) = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .max_by_key(|(camera, _)| camera.order)
+    else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, state_machine.pointer) else {
+        return;
+    };
+    let active = state_machine
+        .get_active_entity()
+        .and_then(|entity| transforms.get(entity).ok())
+        .map(|transform| transform.translation());
+    // Draw the pointer at the same distance as the active entity.
+    let distance = active
+        .map(|x| (x - ray.origin).dot(*ray.direction))
+        .unwrap_or(1.0);
+    let at = |ray: Ray3d| -> Vec3 { ray.origin + *ray.direction * distance };
+    let pointer = at(ray);
+    let radius = camera
+        .viewport_to_world(camera_transform, state_machine.pointer + Vec2::X * 6.0)
+        .map(|ray| at(ray).distance(pointer))
+        .unwrap_or(0.0);
+    let color: Color = match state_machine.active_state() {
+        EntityPickingState::None => WHITE.into(),
+        EntityPickingState::Hover => YELLOW.into(),
+        EntityPickingState::Pressed => RED.into(),
+    };
+    gizmos.sphere(pointer, radius, color);
+    if let Some(active) = active {
+        gizmos.line(pointer, active, color);
+    }
+}
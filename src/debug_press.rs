@@ -0,0 +1,34 @@
+use bevy::{
+    ecs::{
+        resource::Resource,
+        system::{Res, ResMut},
+    },
+    input::{ButtonInput, keyboard::KeyCode},
+};
+
+use crate::{PickingStateMachine, PointerSource};
+
+/// Optional resource that toggles a synthetic press on the hovered entity with a key,
+/// for testing press driven logic without a mouse.
+///
+/// Unlike [`PickingStateMachine::keyboard_press`], the press is not
+/// [`synthetic`](PickingStateMachine::synthetic).
+///
+/// Requires the `debug` feature.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct DebugPressKey(pub KeyCode);
+
+pub(crate) fn debug_press_system(
+    key: Res<DebugPressKey>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut state_machine: ResMut<PickingStateMachine>,
+) {
+    if !input.just_pressed(key.0) {
+        return;
+    }
+    if state_machine.is_pressing() {
+        state_machine.virtual_release();
+    } else if state_machine.is_hovering() {
+        state_machine.virtual_press(PointerSource::Programmatic);
+    }
+}
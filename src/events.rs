@@ -0,0 +1,56 @@
+use bevy::ecs::{
+    entity::Entity,
+    event::{EntityEvent, EntityTrigger, Event},
+    message::{Message, MessageWriter},
+    system::{Commands, Res},
+};
+
+use crate::{AllPickingStateMachines, PickingStateMachinePlugin, PickingTransition};
+
+/// A [`PickingTransition`] with its entity, written as a [`Message`] every frame
+/// for tools that inspect message resources.
+///
+/// Observers receive the [`PickingTransition`] itself.
+#[derive(Debug, Clone, Message)]
+pub struct PickingTransitionEvent {
+    pub entity: Entity,
+    pub transition: PickingTransition,
+}
+
+impl Event for PickingTransition {
+    type Trigger<'a> = EntityTrigger;
+}
+
+/// Triggered on [`PickingTransition::entity`].
+impl EntityEvent for PickingTransition {
+    fn event_target(&self) -> Entity {
+        self.entity()
+    }
+}
+
+pub(crate) fn emit_events_enabled(settings: Res<PickingStateMachinePlugin>) -> bool {
+    settings.emit_events
+}
+
+pub(crate) fn picking_message_system(
+    state_machines: AllPickingStateMachines,
+    mut writer: MessageWriter<PickingTransition>,
+    mut event_writer: MessageWriter<PickingTransitionEvent>,
+) {
+    for transition in state_machines.iter_transitions() {
+        event_writer.write(PickingTransitionEvent {
+            entity: transition.entity(),
+            transition: transition.clone(),
+        });
+        writer.write(transition);
+    }
+}
+
+pub(crate) fn picking_event_system(
+    state_machines: AllPickingStateMachines,
+    mut commands: Commands,
+) {
+    for transition in state_machines.iter_transitions() {
+        commands.trigger(transition);
+    }
+}
@@ -0,0 +1,67 @@
+use bevy::ecs::{
+    component::Component,
+    entity::{Entity, EntityHashMap},
+    hierarchy::Children,
+    system::{Commands, Query},
+};
+
+use crate::{
+    AllPickingStateMachines, GlobalPickingState, propagation::PropagatedPickingStateMachine,
+};
+
+/// Kind of [`Highlighted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Hover,
+    Press,
+}
+
+/// Inserted on the active entity of each window and entities equivalent to it
+/// by [`PickingPropagation`](crate::propagation::PickingPropagation),
+/// removed when neither hovered nor pressed.
+///
+/// Requires the `highlight` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Highlighted {
+    pub kind: HighlightKind,
+}
+
+pub(crate) fn highlight_system(
+    mut commands: Commands,
+    propagated: PropagatedPickingStateMachine,
+    state_machines: AllPickingStateMachines,
+    children: Query<&Children>,
+    highlighted: Query<(Entity, &Highlighted)>,
+) {
+    if state_machines.iter().all(|x| x.previous == x.current) {
+        return;
+    }
+    // Only the previous highlights and the hierarchy around the active entities are visited,
+    // a press in any window wins over a hover in another.
+    let mut desired = EntityHashMap::<HighlightKind>::default();
+    for state_machine in state_machines.iter() {
+        let (active, kind) = match state_machine.current {
+            GlobalPickingState::None => continue,
+            GlobalPickingState::Hover { entity } => (entity, HighlightKind::Hover),
+            GlobalPickingState::Pressed { entity } | GlobalPickingState::Dragging { entity } => {
+                (entity, HighlightKind::Press)
+            }
+        };
+        for entity in propagated.equivalent_entities(active, &children) {
+            let current = desired.entry(entity).or_insert(kind);
+            if kind == HighlightKind::Press {
+                *current = kind;
+            }
+        }
+    }
+    for (entity, _) in &highlighted {
+        if !desired.contains_key(&entity) {
+            commands.entity(entity).try_remove::<Highlighted>();
+        }
+    }
+    for (entity, kind) in desired {
+        if highlighted.get(entity).map(|(_, x)| x.kind) != Ok(kind) {
+            commands.entity(entity).try_insert(Highlighted { kind });
+        }
+    }
+}
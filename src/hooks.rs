@@ -0,0 +1,135 @@
+use bevy::{
+    ecs::{
+        entity::Entity,
+        resource::Resource,
+        system::{In, Res, ResMut},
+    },
+    input::mouse::MouseButton,
+    math::Vec2,
+};
+
+use crate::{
+    AllPickingStateMachines, PickingStateMachine, PickingStateMachines, PickingTransition,
+};
+
+type ButtonCallback = Box<dyn Fn(Entity, MouseButton) + Send + Sync>;
+type EntityCallback = Box<dyn Fn(Entity) + Send + Sync>;
+type CommitCallback = Box<dyn Fn(MouseButton, Vec2) -> bool + Send + Sync>;
+
+/// Optional resource for triggering audio or haptic feedback from a single place.
+///
+/// Callbacks are invoked after the state machine resolves each frame.
+#[derive(Default, Resource)]
+pub struct InteractionFeedback {
+    /// Called on [`PickingTransition::Pressed`].
+    pub on_press: Option<ButtonCallback>,
+    /// Called on [`PickingTransition::Released`], inside or outside.
+    pub on_release: Option<ButtonCallback>,
+    /// Called on [`PickingTransition::HoverEnter`].
+    pub on_hover: Option<EntityCallback>,
+}
+
+impl InteractionFeedback {
+    pub fn on_press(mut self, f: impl Fn(Entity, MouseButton) + Send + Sync + 'static) -> Self {
+        self.on_press = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_release(mut self, f: impl Fn(Entity, MouseButton) + Send + Sync + 'static) -> Self {
+        self.on_release = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_hover(mut self, f: impl Fn(Entity) + Send + Sync + 'static) -> Self {
+        self.on_hover = Some(Box::new(f));
+        self
+    }
+}
+
+/// Optional resource deciding whether a button down commits to a press,
+/// given the button and pointer position.
+///
+/// While this returns false, the button is ignored and the entity stays hovered,
+/// the press commits on the first frame this returns true, e.g. when pen pressure crosses a threshold.
+#[derive(Resource)]
+pub struct PressCommit(pub CommitCallback);
+
+impl PressCommit {
+    pub fn new(f: impl Fn(MouseButton, Vec2) -> bool + Send + Sync + 'static) -> Self {
+        PressCommit(Box::new(f))
+    }
+}
+
+/// Optional resource replacing device input, for scripted examples and tests.
+///
+/// Each value overrides its device read while set,
+/// combine with injected [`PointerHits`](bevy::picking::backend::PointerHits)
+/// to script interactions without real devices.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct PickingInputOverride {
+    /// Pointer position, relative to `PickingStateMachinePlugin::pointer_origin`.
+    pub pointer: Option<Vec2>,
+    /// Mouse buttons held, an empty list holds nothing.
+    ///
+    /// A button is just pressed on the first frame it appears in the list.
+    pub buttons: Option<Vec<MouseButton>>,
+}
+
+/// Sets [`PickingStateMachine::suppressed`] of every window from a piped system,
+/// e.g. one returning `wants_pointer_input` of `egui`,
+/// so world picking exits or releases and stays idle while a UI on top has the pointer.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_picking_state_machine::{PickingStateMachineSystems, suppress_picking};
+/// #[derive(Resource)]
+/// struct UiWantsPointer(bool);
+///
+/// fn ui_wants_pointer(ui: Res<UiWantsPointer>) -> bool {
+///     ui.0
+/// }
+///
+/// # let mut app = App::new();
+/// app.add_systems(
+///     PreUpdate,
+///     ui_wants_pointer
+///         .pipe(suppress_picking)
+///         .before(PickingStateMachineSystems::Resolve),
+/// );
+/// ```
+pub fn suppress_picking(
+    In(suppressed): In<bool>,
+    mut state_machine: ResMut<PickingStateMachine>,
+    mut machines: ResMut<PickingStateMachines>,
+) {
+    state_machine.suppressed = suppressed;
+    for machine in machines.values_mut() {
+        machine.suppressed = suppressed;
+    }
+}
+
+pub(crate) fn interaction_feedback_system(
+    feedback: Res<InteractionFeedback>,
+    state_machines: AllPickingStateMachines,
+) {
+    for transition in state_machines.iter_transitions() {
+        match transition {
+            PickingTransition::Pressed { entity, button, .. } => {
+                if let Some(f) = &feedback.on_press {
+                    f(entity, button)
+                }
+            }
+            PickingTransition::Released { entity, button, .. } => {
+                if let Some(f) = &feedback.on_release {
+                    f(entity, button)
+                }
+            }
+            PickingTransition::HoverEnter { entity } => {
+                if let Some(f) = &feedback.on_hover {
+                    f(entity)
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -1,43 +1,198 @@
 #![doc = include_str!("../README.md")]
 #![allow(clippy::collapsible_if)]
 #![allow(clippy::too_many_arguments)]
-use core::f32;
-use std::cmp::Reverse;
+use core::ops::{Deref, DerefMut};
+use core::{f32, iter};
+use std::cmp::{Ordering, Reverse};
+#[cfg(feature = "debug")]
+use std::collections::VecDeque;
+mod bounds;
+mod builder;
+#[cfg(feature = "channel")]
+mod channel;
+pub mod conditions;
+#[cfg(feature = "debug_gizmos")]
+mod debug;
+#[cfg(feature = "debug")]
+mod debug_press;
+mod events;
+#[cfg(feature = "highlight")]
+mod highlight;
+mod hooks;
 mod local;
 pub mod propagation;
+mod reactive;
+#[cfg(test)]
+mod tests;
 mod transitions;
-pub use local::{ButtonFilter, PickPriority};
-pub use transitions::PickingTransition;
+pub use bounds::ActiveGroupBounds;
+pub use builder::PickingStateMachinePluginBuilder;
+#[cfg(feature = "channel")]
+pub use channel::TransitionChannel;
+#[cfg(feature = "debug_gizmos")]
+pub use debug::PickingDebugPlugin;
+#[cfg(feature = "debug")]
+pub use debug_press::DebugPressKey;
+pub use events::PickingTransitionEvent;
+#[cfg(feature = "highlight")]
+pub use highlight::{HighlightKind, Highlighted};
+pub use hooks::{InteractionFeedback, PickingInputOverride, PressCommit, suppress_picking};
+pub use local::{
+    ActivationMode, ButtonFilter, ButtonFilters, DefaultButtonFilter, ExtraButtons,
+    MultiClickConfig, PickPriority, PickTier, Selectable, SelectionState, ToggleButton,
+};
+pub use propagation::{
+    PickHandler, PickRoot, PickingPropagation, PropagatedPickingStateMachine, PropagationCache,
+};
+pub use reactive::{PickReactive, PickReactivePlugin, apply_pick_reactions};
+pub use transitions::{EndReason, LostReason, PickingTransition, PressEnd};
+
+use smallvec::SmallVec;
 
 use bevy::{
     app::{Plugin, PreUpdate},
+    camera::{NormalizedRenderTarget, RenderTarget},
     ecs::{
-        entity::Entity,
+        change_detection::DetectChanges,
+        entity::{ContainsEntity, Entities, Entity, EntityHashMap, EntityHashSet},
         message::MessageReader,
-        query::With,
+        query::{Has, Spawned, With},
         resource::Resource,
-        schedule::IntoScheduleConfigs,
-        system::{In, IntoSystem, Query, Res, ResMut},
+        schedule::{IntoScheduleConfigs, SystemSet, common_conditions::resource_exists},
+        system::{In, IntoSystem, Local, Query, Res, ResMut, SystemParam},
     },
     input::{
         ButtonInput,
-        mouse::{MouseButton, MouseMotion},
+        gamepad::{Gamepad, GamepadButton},
+        keyboard::KeyCode,
+        mouse::{MouseButton, MouseMotion, MouseScrollUnit, MouseWheel},
+        touch::Touches,
+    },
+    math::{Rect, Vec2},
+    picking::{
+        PickingSystems,
+        backend::{HitData, PointerHits},
     },
-    math::Vec2,
-    picking::{PickingSystems, backend::PointerHits},
     time::{Time, Virtual},
     window::{PrimaryWindow, Window},
 };
+#[cfg(feature = "reflect")]
+use bevy::{ecs::reflect::ReflectResource, reflect::Reflect};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Plugin for [`PickingStateMachine`].
-#[derive(Debug, Clone, Resource)]
+///
+/// Prefer [`PickingStateMachinePlugin::builder`] over struct literals,
+/// which break whenever a field is added.
+#[derive(Debug, Clone, PartialEq, Resource)]
 pub struct PickingStateMachinePlugin {
     /// Only buttons in this list will be considered.
     ///
     /// By default we only consider the left mouse button.
+    ///
+    /// Buttons not in this list never participate in any state change,
+    /// holding one does not cancel or interrupt the current hover or press.
+    ///
+    /// Removing the button of the current press at runtime cancels the press.
     pub allowed_buttons: Vec<MouseButton>,
     /// If true, pressing multiple buttons will immediately cancel `Hover` to `None`.
     pub cancel_hover: bool,
+    /// Decides what happens when multiple allowed buttons are held, cancellation by default.
+    pub multi_button_policy: MultiButtonPolicy,
+    /// If true, while pressed, the press follows the pointer to whatever entity is under it,
+    /// emitting a release on the old entity and a press on the new one.
+    ///
+    /// Useful for painting across cells. By default presses are sticky.
+    pub acquire_while_pressed: bool,
+    /// If true, an entity spawned under the pointer while a button is held
+    /// can be pressed without a new button down.
+    ///
+    /// An existing press is still sticky, only a held button outside of any entity is affected.
+    pub press_acquire_on_spawn: bool,
+    /// Origin of [`PickingStateMachine::pointer`], by default the same as the window.
+    pub pointer_origin: PointerOrigin,
+    /// Decides which pick wins when several picks have exactly the same priority.
+    pub within_batch_tiebreak: HitTiebreak,
+    /// If true, the hovered entity is kept when another pick has exactly the same priority,
+    /// overriding `within_batch_tiebreak`. True by default to avoid flickering.
+    pub prefer_current_on_tie: bool,
+    /// If true, emit [`PickingTransition::HitUpdate`] each frame the pressed entity's hit data changes.
+    pub emit_hit_updates: bool,
+    /// Seconds to extrapolate [`PickingStateMachine::pointer_predicted`] by.
+    pub prediction_time: f32,
+    /// If positive, hover is re-evaluated at most once per this many seconds.
+    ///
+    /// Presses and releases are still resolved every frame. Skipped frames keep
+    /// [`PickingStateMachine::has_hits_this_frame`] and [`PickingStateMachine::shadow_hover`]
+    /// of the last resolve.
+    pub resolve_interval: f32,
+    /// Maximum seconds between consecutive releases for them to count as a multi-click.
+    ///
+    /// [`PickingStateMachine::press_on_recent`] instead measures from a release to the next press.
+    /// Can be overridden per entity by [`MultiClickConfig`].
+    pub multi_click_interval: f32,
+    /// Maximum distance in pixels between consecutive releases for them to count as a multi-click.
+    pub multi_click_radius: f32,
+    /// If true, releasing the extra buttons of a chord while still holding the original button
+    /// resumes the original press, instead of waiting for all buttons to be released.
+    pub resume_after_chord: bool,
+    /// If positive, a release followed by a press of the same button within this many seconds
+    /// is treated as a continuous press, bridging spurious releases on some trackpads.
+    ///
+    /// Releases are reported this much later as a result.
+    pub release_debounce: f32,
+    /// Maximum pointer movement in pixels during a bridged release.
+    pub release_debounce_radius: f32,
+    /// If true, skip resolving hover on frames where [`PickingStateMachine::is_quiescent`],
+    /// input is still read every frame so new presses and movements are not missed.
+    ///
+    /// Entities moving under a still pointer will not be hovered until the pointer moves.
+    pub skip_when_quiescent: bool,
+    /// If true, releasing inside the pressed entity also emits
+    /// [`PickingTransition::HoverEnter`] after the [`PickingTransition::Released`].
+    pub rehover_on_release: bool,
+    /// If true, trigger each [`PickingTransition`] on its entity, in order,
+    /// e.g. for `app.add_observer(|event: On<PickingTransition>| ...)`.
+    ///
+    /// [`PickingTransition`] and [`PickingTransitionEvent`] messages are written regardless.
+    pub emit_events: bool,
+    /// Distance in pixels the pointer must move from the press position
+    /// before a press becomes [`GlobalPickingState::Dragging`].
+    ///
+    /// Infinite by default, which disables drags.
+    /// Releasing a drag does not emit [`PickingTransition::Clicked`],
+    /// so presses that move further than this stop producing clicks.
+    pub drag_threshold: f32,
+    /// Seconds the pointer must stay on a drop target while dragging
+    /// to emit [`PickingTransition::DropTargetArmed`], e.g. for spring-loaded folders.
+    pub drop_dwell: f32,
+    /// If set, at most this many [`PointerHits`] are considered per frame, in order,
+    /// the rest are ignored.
+    pub max_hit_batches: Option<usize>,
+    /// If set, seconds a press must be held within `drag_threshold`
+    /// of its position to emit [`PickingTransition::LongPress`].
+    pub long_press_duration: Option<f32>,
+    /// If true, the cursor is out of bounds in windows that are not focused.
+    ///
+    /// Prevents background windows from reacting to a cursor over an overlapping window.
+    pub respect_window_focus: bool,
+    /// Determines the state of a press while the pointer is outside of its entity.
+    pub press_retention: PressRetention,
+    /// If true, despawning the active entity resets the state to `None`,
+    /// emitting [`PickingTransition::Cancelled`] if pressed, then [`PickingTransition::Lost`].
+    ///
+    /// True by default, otherwise the state keeps the despawned entity
+    /// until it would otherwise be exited, e.g. on release.
+    pub despawn_resets: bool,
+    /// If true, emit [`PickingTransition::Scrolled`] on the hovered or pressed entity
+    /// when the mouse wheel scrolls.
+    pub route_scroll: bool,
+    /// Pixels per line for wheels reporting [`MouseScrollUnit::Line`].
+    pub line_to_pixel: f32,
+    /// If set, this gamepad button presses the hovered entity like [`PickingStateMachine::keyboard_press`],
+    /// with [`PointerSource::Gamepad`], and releases it when released.
+    pub gamepad_confirm: Option<GamepadButton>,
 }
 
 impl Default for PickingStateMachinePlugin {
@@ -45,6 +200,33 @@ impl Default for PickingStateMachinePlugin {
         Self {
             allowed_buttons: vec![MouseButton::Left],
             cancel_hover: false,
+            multi_button_policy: MultiButtonPolicy::Cancel,
+            acquire_while_pressed: false,
+            press_acquire_on_spawn: false,
+            pointer_origin: PointerOrigin::TopLeft,
+            within_batch_tiebreak: HitTiebreak::FirstHit,
+            prefer_current_on_tie: true,
+            emit_hit_updates: false,
+            prediction_time: 0.0,
+            resolve_interval: 0.0,
+            multi_click_interval: 0.5,
+            multi_click_radius: 4.0,
+            resume_after_chord: false,
+            release_debounce: 0.0,
+            release_debounce_radius: 4.0,
+            skip_when_quiescent: false,
+            rehover_on_release: false,
+            emit_events: false,
+            drag_threshold: f32::INFINITY,
+            drop_dwell: 0.5,
+            max_hit_batches: None,
+            long_press_duration: None,
+            respect_window_focus: false,
+            press_retention: PressRetention::Sticky,
+            despawn_resets: true,
+            route_scroll: false,
+            line_to_pixel: 20.0,
+            gamepad_confirm: None,
         }
     }
 }
@@ -53,18 +235,89 @@ impl Plugin for PickingStateMachinePlugin {
     fn build(&self, app: &mut bevy::app::App) {
         app.insert_resource(self.clone());
         app.init_resource::<PickingStateMachine>();
+        app.init_resource::<PickingStateMachines>();
+        #[cfg(feature = "reflect")]
+        app.register_type::<PickingStateMachine>()
+            .register_type::<PickingTransition>();
+        app.add_message::<PickingTransition>();
+        app.add_message::<PickingTransitionEvent>();
+        app.configure_sets(
+            PreUpdate,
+            (
+                PickingStateMachineSystems::Resolve,
+                PickingStateMachineSystems::Dispatch,
+            )
+                .chain()
+                .in_set(PickingSystems::Hover),
+        );
+        app.add_systems(
+            PreUpdate,
+            (
+                picking_window_system
+                    .pipe(picking_button_system)
+                    .pipe(picking_state_machine_system),
+                picking_scroll_system.run_if(route_scroll_enabled),
+            )
+                .chain()
+                .in_set(PickingStateMachineSystems::Resolve),
+        );
         app.add_systems(
             PreUpdate,
-            picking_window_system
-                .pipe(picking_button_system)
-                .pipe(picking_state_machine_system)
+            hooks::interaction_feedback_system
+                .run_if(resource_exists::<InteractionFeedback>)
+                .in_set(PickingStateMachineSystems::Dispatch),
+        );
+        app.add_systems(
+            PreUpdate,
+            (
+                events::picking_message_system,
+                events::picking_event_system.run_if(events::emit_events_enabled),
+            )
+                .chain()
+                .in_set(PickingStateMachineSystems::Dispatch),
+        );
+        #[cfg(feature = "debug")]
+        app.add_systems(
+            PreUpdate,
+            debug_press::debug_press_system
+                .run_if(resource_exists::<DebugPressKey>)
+                .before(PickingStateMachineSystems::Resolve)
                 .in_set(PickingSystems::Hover),
         );
+        app.add_systems(
+            PreUpdate,
+            propagation::propagation_cache_system
+                .run_if(resource_exists::<PropagationCache>)
+                .in_set(PickingStateMachineSystems::Resolve),
+        );
+        #[cfg(feature = "channel")]
+        app.add_systems(
+            PreUpdate,
+            channel::transition_channel_system
+                .run_if(resource_exists::<TransitionChannel>)
+                .in_set(PickingStateMachineSystems::Dispatch),
+        );
+        #[cfg(feature = "highlight")]
+        app.add_systems(
+            PreUpdate,
+            highlight::highlight_system.in_set(PickingStateMachineSystems::Dispatch),
+        );
     }
 }
 
+/// System sets of [`PickingStateMachinePlugin`], both in [`PickingSystems::Hover`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub enum PickingStateMachineSystems {
+    /// Reads input and updates [`PickingStateMachine`].
+    Resolve,
+    /// Reacts to the transitions of the current frame.
+    Dispatch,
+}
+
 /// Picking state of an entity.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EntityPickingState {
     #[default]
     None,
@@ -73,7 +326,9 @@ pub enum EntityPickingState {
 }
 
 /// Picking state globally.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GlobalPickingState {
     #[default]
     None,
@@ -83,6 +338,13 @@ pub enum GlobalPickingState {
     Pressed {
         entity: Entity,
     },
+    /// Pressed and moved further than `PickingStateMachinePlugin::drag_threshold`.
+    ///
+    /// Kept when the pointer leaves the entity like `Pressed`,
+    /// but unlike `Pressed` not handed to other entities if `acquire_while_pressed` is set.
+    Dragging {
+        entity: Entity,
+    },
 }
 
 impl GlobalPickingState {
@@ -91,20 +353,193 @@ impl GlobalPickingState {
             GlobalPickingState::None => None,
             GlobalPickingState::Hover { entity } => Some(*entity),
             GlobalPickingState::Pressed { entity } => Some(*entity),
+            GlobalPickingState::Dragging { entity } => Some(*entity),
         }
     }
 }
 
+/// Summary of the current interaction, see [`PickingStateMachine::interaction_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InteractionKind {
+    Idle,
+    Hovering(Entity),
+    Pressing(Entity),
+    Dragging(Entity),
+}
+
 /// State for a button press.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PressState {
     pub button: MouseButton,
     pub position: Vec2,
+    /// Value of [`PickingStateMachine::now`] when the press started.
     pub time: f32,
+    /// Input path that produced the press.
+    pub source: PointerSource,
+    /// Keyboard modifiers held when the press started.
+    pub modifiers: Modifiers,
+}
+
+/// Keyboard modifiers held at the time of a press, either side of the keyboard counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(1);
+    pub const CTRL: Modifiers = Modifiers(2);
+    pub const ALT: Modifiers = Modifiers(4);
+    pub const SUPER: Modifiers = Modifiers(8);
+
+    /// Read the modifiers currently held.
+    pub fn from_input(input: &ButtonInput<KeyCode>) -> Self {
+        let mut result = Modifiers::NONE;
+        for (modifier, keys) in [
+            (Modifiers::SHIFT, [KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            (
+                Modifiers::CTRL,
+                [KeyCode::ControlLeft, KeyCode::ControlRight],
+            ),
+            (Modifiers::ALT, [KeyCode::AltLeft, KeyCode::AltRight]),
+            (Modifiers::SUPER, [KeyCode::SuperLeft, KeyCode::SuperRight]),
+        ] {
+            if input.any_pressed(keys) {
+                result = result.with(modifier);
+            }
+        }
+        result
+    }
+
+    /// Returns the union of both modifiers.
+    pub const fn with(self, other: Modifiers) -> Self {
+        Modifiers(self.0 | other.0)
+    }
+
+    /// Returns true if all modifiers in `other` are held.
+    pub const fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn shift(self) -> bool {
+        self.contains(Modifiers::SHIFT)
+    }
+
+    pub const fn ctrl(self) -> bool {
+        self.contains(Modifiers::CTRL)
+    }
+
+    pub const fn alt(self) -> bool {
+        self.contains(Modifiers::ALT)
+    }
+
+    pub const fn super_key(self) -> bool {
+        self.contains(Modifiers::SUPER)
+    }
+}
+
+/// Input device or path that produced an interaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PointerSource {
+    #[default]
+    Mouse,
+    Touch,
+    Gamepad,
+    /// Produced by calling methods like [`PickingStateMachine::keyboard_press`],
+    /// or by a pointer from [`PickingInputOverride`].
+    Programmatic,
+}
+
+/// Origin of the pointer space.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PointerOrigin {
+    /// Same as [`Window::cursor_position`].
+    #[default]
+    TopLeft,
+    /// Flip `y` against the window height.
+    BottomLeft,
+}
+
+/// Decides which of the picks with equal `(order, depth)` wins.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HitTiebreak {
+    /// The first pick in backend order wins.
+    #[default]
+    FirstHit,
+    /// The last pick in backend order wins.
+    LastHit,
+}
+
+/// Decides what happens when multiple allowed buttons are held.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultiButtonPolicy {
+    /// Cancel the current interaction until all buttons are released.
+    #[default]
+    Cancel,
+    /// The first held button in `allowed_buttons` order is tracked and the rest are ignored.
+    ///
+    /// The tracked button is kept while held, when it is released,
+    /// the next held button presses again on the following frame.
+    Priority,
+    /// The earliest held button is tracked and the rest are ignored.
+    ///
+    /// When the tracked button is released while another allowed button is held,
+    /// the press continues with that button instead of releasing,
+    /// emitting [`PickingTransition::TrackingSwitched`].
+    FirstWins,
+}
+
+/// State of a press while the pointer is outside of its entity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PressRetention {
+    /// The entity stays pressed.
+    #[default]
+    Sticky,
+    /// The entity is not pressed while the pointer is outside of it,
+    /// emitting [`PickingTransition::DragOutside`] and [`PickingTransition::DragReturn`]
+    /// instead of releasing and pressing again.
+    ///
+    /// Releasing outside emits `Released` on the entity as usual,
+    /// as does pressing another entity with `acquire_while_pressed`.
+    /// Drags continue while outside, until the press ends.
+    ReleaseWhileOutside,
+}
+
+/// Axis a drag is locked to, see [`PickingStateMachine::locked_drag_axis`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DragAxis {
+    Horizontal,
+    Vertical,
+}
+
+/// Reason new entities cannot be acquired, see [`PickingStateMachine::gate_reason`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GateReason {
+    /// Multiple buttons are or were pressed at the same time.
+    PostCancellation,
+    /// A button is held, pressing an entity or nothing.
+    Pressed,
 }
 
 /// Determines who owns the cursor.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CursorOwner {
     /// Represents curser is being controlled by the mouse.
     #[default]
@@ -113,8 +548,36 @@ pub enum CursorOwner {
     Keyboard,
 }
 
+/// Reason a press did not acquire a target.
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NoAcquireReason {
+    /// Multiple buttons are or were pressed at the same time.
+    PostCancellation,
+    /// The entity hit did not allow the button, via [`ButtonFilter`] or [`ExtraButtons`].
+    Filtered,
+    /// Nothing is under the pointer.
+    NoHit,
+    /// [`PickingStateMachine::suppressed`] is set.
+    Suppressed,
+    /// A button is held without pressing an entity, see [`PickingStateMachine::acquisition_gated`].
+    AcquireGated,
+}
+
+/// Number of hover enters on the same entity within a second that counts as flickering.
+#[cfg(feature = "debug")]
+pub const FLICKER_THRESHOLD: usize = 5;
+
+/// Button of presses without a button, e.g. by [`PickingStateMachine::keyboard_press`].
+const VIRTUAL_BUTTON: MouseButton = MouseButton::Other(u16::MAX);
+
 /// Global state machine for `bevy_picking`.
+///
+/// The resource tracks the primary window, see [`PickingStateMachines`] for other windows.
 #[derive(Debug, Clone, Default, Resource)]
+#[cfg_attr(feature = "reflect", derive(Reflect), reflect(Resource))]
 #[non_exhaustive]
 pub struct PickingStateMachine {
     /// State of the previous frame.
@@ -123,6 +586,25 @@ pub struct PickingStateMachine {
     pub current: GlobalPickingState,
     /// Pointer position.
     pub pointer: Vec2,
+    /// Window [`PickingStateMachine::pointer`] is in, see [`PickingStateMachines`].
+    ///
+    /// `None` if out of bounds.
+    pub window: Option<Entity>,
+    /// Device [`PickingStateMachine::pointer`] is read from this frame,
+    /// `None` if out of bounds.
+    ///
+    /// The cursor is preferred over touches if both are present.
+    pub pointer_source: Option<PointerSource>,
+    /// Pointer position of the previous frame.
+    ///
+    /// Same as `pointer` when the pointer just entered bounds.
+    pub previous_pointer: Vec2,
+    /// Pointer velocity in pixels per second.
+    pub pointer_velocity: Vec2,
+    /// Pointer position extrapolated from velocity by `PickingStateMachinePlugin::prediction_time`.
+    ///
+    /// Same as `pointer` when out of bounds.
+    pub pointer_predicted: Vec2,
     /// If mouse is pressed, contains position, button and time of the button press.
     ///
     /// # Note
@@ -134,11 +616,22 @@ pub struct PickingStateMachine {
     /// If true, [`PickingStateMachine::pointer`]
     /// is not retrieved from the current frame.
     pub pointer_is_out_of_bounds: bool,
+    /// Distance in pixels the pointer traveled while the button of the current press is held,
+    /// reset on each new press.
+    ///
+    /// Does not accumulate while out of bounds.
+    pub press_travel: f32,
     /// True if multiple valid buttons are pressed as the same time.
     /// Lasts until all valid buttons are released.
     pub is_post_cancellation_state: bool,
+    /// Buttons held when the current cancellation was detected,
+    /// the tracked button first.
+    ///
+    /// Empty if not cancelled by multiple buttons, e.g. by [`PickingStateMachine::request_cancel`].
+    pub chord: SmallVec<[MouseButton; 2]>,
     /// An internal event channel for picking events.
     ///
+    /// At most one transition of each kind is queued per frame.
     /// Use `as_ref` or `iter` to access items.
     pub transitions: Vec<PickingTransition>,
     /// Determines who owns the cursor.
@@ -148,8 +641,181 @@ pub struct PickingStateMachine {
     /// the state machine is user controlled, until the mouse moves and triggers
     /// either hover or click, in which case reverts back to [`CursorOwner::Mouse`].
     pub owner: CursorOwner,
-    /// Cached elapsed seconds.
+    /// Elapsed seconds of [`Time<Virtual>`] minus the seconds spent with
+    /// [`PickingStateMachine::timers_paused`] set, used by all duration based features.
+    ///
+    /// Set each frame before resolving, equal across windows unless one of them was paused.
     pub now: f32,
+    /// If true, freezes [`PickingStateMachine::now`] and thus gesture timing,
+    /// regardless of the state of [`Time<Virtual>`].
+    pub timers_paused: bool,
+    /// Value of [`PickingStateMachine::now`] at the last transition or pointer movement.
+    pub last_activity: f32,
+    /// Value of [`PickingStateMachine::now`] when the current hover began,
+    /// `None` if not hovering.
+    ///
+    /// Restarts when the hovered entity changes.
+    /// Like the rest of the crate this follows [`Time<Virtual>`].
+    pub hover_started: Option<f32>,
+    /// True if the tracked button is released this frame, regardless of what's under the pointer.
+    pub button_released: bool,
+    /// Number of consecutive clicks on the last clicked entity,
+    /// within `multi_click_interval` and `multi_click_radius` of each other.
+    pub click_count: u32,
+    /// True if any entity is under the pointer this frame, even if it cannot be acquired.
+    ///
+    /// Frames skipped by `resolve_interval` keep the value of the last resolve.
+    pub has_hits_this_frame: bool,
+    /// Camera of the hit that acquired the active entity.
+    ///
+    /// Kept while a press sticks outside of its entity, `None` if nothing is active.
+    pub current_camera: Option<Entity>,
+    /// If true, nothing is acquired and the active entity exits or releases,
+    /// until set back to false.
+    ///
+    /// Use this to block world picking while a UI layer on top wants the pointer,
+    /// e.g. set it to `wants_pointer_input()` of `egui` with [`suppress_picking`]
+    /// before [`PickingStateMachineSystems::Resolve`].
+    pub suppressed: bool,
+    /// True if the transitions of this frame are produced by
+    /// the `keyboard_*` methods, `commit_drag` or `abort_drag`,
+    /// as opposed to pointer, `gamepad_confirm` or `DebugPressKey` input.
+    pub synthetic: bool,
+    /// Reason the last frame an allowed button went down failed to set
+    /// [`GlobalPickingState::Pressed`], including during cancellation.
+    ///
+    /// Reset to `None` on a successful press.
+    #[cfg(feature = "debug")]
+    pub last_no_acquire_reason: Option<NoAcquireReason>,
+    /// Number of times the most recently hovered entity re-entered hover
+    /// [`FLICKER_THRESHOLD`] times within a second.
+    ///
+    /// This usually indicates an unstable layout and warns once.
+    #[cfg(feature = "debug")]
+    pub flicker_count: u32,
+    /// Entity and recent hover enter times, for `flicker_count`.
+    #[cfg(feature = "debug")]
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    flicker: Option<(Entity, VecDeque<f32>)>,
+    /// Last hit data on the pressed entity, for `emit_hit_updates`.
+    last_hit: Option<HitData>,
+    /// [`ToggleButton`] entities that are toggled on.
+    #[cfg_attr(feature = "reflect", reflect(ignore))]
+    toggled: EntityHashSet,
+    /// Time of the last unthrottled resolve, for `resolve_interval`.
+    last_resolve: f32,
+    /// True if the pointer moved or left the window this frame.
+    pointer_moved: bool,
+    /// Time and pointer position of a release pending `release_debounce`.
+    release_pending: Option<(f32, Vec2)>,
+    /// State at the end of the last resolve.
+    resolved: GlobalPickingState,
+    /// Entity, time, pointer position and button of the last inside release.
+    last_release: Option<(Entity, f32, Vec2, MouseButton)>,
+    /// Set if the current press began on the entity last released within `multi_click_interval`.
+    recent_press: Option<Entity>,
+    /// Each new maximum of the current drag offset along either axis,
+    /// with its dominant axis, for `locked_drag_axis`.
+    drag_axis: Vec<(f32, Option<DragAxis>)>,
+    /// Pointer movement since the previous frame, for `pointer_delta`.
+    pointer_delta: Vec2,
+    /// Topmost entity under the pointer, regardless of presses and gating.
+    shadow_hover: Option<Entity>,
+    /// Topmost entity under the pointer while dragging, for `DragEnter`.
+    drag_over: Option<Entity>,
+    /// Time `drag_over` last changed, for `drop_dwell`.
+    drag_over_since: f32,
+    /// True if `drag_over` emitted `DropTargetArmed`.
+    drop_armed: bool,
+    /// Entity of a press while the pointer is outside of it,
+    /// for `PressRetention::ReleaseWhileOutside`.
+    retained: Option<Entity>,
+    /// True if the retained press was dragging when the pointer left.
+    retained_dragging: bool,
+    /// Time the active entity became pressed, for `hold_progress`.
+    pressed_since: f32,
+    /// True if the current press emitted `LongPress`.
+    long_pressed: bool,
+    /// Set by `commit_drag` (true) or `abort_drag` (false) until the next resolve.
+    drag_end: Option<bool>,
+    /// Set by `request_cancel` until the next resolve.
+    cancel_requested: bool,
+    /// Seconds of [`Time<Virtual>`] elapsed while `timers_paused` is set.
+    paused_time: f32,
+    /// Copy of `PickingStateMachinePlugin::allowed_buttons` at the last resolve, for `would_press`.
+    allowed_buttons: Vec<MouseButton>,
+    /// Set by the `keyboard_*` methods until the next resolve, for `synthetic`.
+    scripted: bool,
+    /// True if the tracked button is released this frame.
+    tracked_just_released: bool,
+    /// True if another button is held when the tracked button is released,
+    /// under [`MultiButtonPolicy::Priority`].
+    promote_pending: bool,
+    /// Button of the press cancelled by the current chord, for `resume_after_chord`.
+    resume_button: Option<MouseButton>,
+    /// Buttons of a handover under [`MultiButtonPolicy::FirstWins`] this frame.
+    tracking_switched: Option<(MouseButton, MouseButton)>,
+    /// Pointer position at the last drag transition, for `DragMove`.
+    drag_pointer: Vec2,
+    /// True if an allowed button went down this frame, for `last_no_acquire_reason`.
+    #[cfg(feature = "debug")]
+    button_went_down: bool,
+}
+
+/// State machines of windows other than the [`PrimaryWindow`], keyed by window.
+///
+/// The primary window's machine is the [`PickingStateMachine`] resource,
+/// which also receives hits of cameras not rendering to a window.
+/// Other windows only receive hits of cameras rendering to them.
+///
+/// Use [`AllPickingStateMachines`] to look up any window including the primary window.
+/// Dispatch systems, e.g. [`PickingTransitionEvent`], handle the transitions of all windows,
+/// [`PropagatedPickingStateMachine`] only reads the [`PickingStateMachine`] resource.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct PickingStateMachines(pub EntityHashMap<PickingStateMachine>);
+
+/// [`SystemParam`] reading the state machines of all windows,
+/// [`PickingStateMachine`] for the primary window and [`PickingStateMachines`] for the others.
+#[derive(SystemParam)]
+pub struct AllPickingStateMachines<'w, 's> {
+    pub primary: Res<'w, PickingStateMachine>,
+    pub others: Res<'w, PickingStateMachines>,
+    primary_window: Query<'w, 's, Entity, With<PrimaryWindow>>,
+}
+
+impl AllPickingStateMachines<'_, '_> {
+    /// Returns the state machine of a window.
+    pub fn get(&self, window: Entity) -> Option<&PickingStateMachine> {
+        if self.primary_window.single().ok() == Some(window) {
+            Some(&self.primary)
+        } else {
+            self.others.get(&window)
+        }
+    }
+
+    /// Iterates over the state machines of all windows, the primary window's first.
+    pub fn iter(&self) -> impl Iterator<Item = &PickingStateMachine> {
+        iter::once(&*self.primary).chain(self.others.values())
+    }
+
+    /// Iterates over the transitions of all windows this frame, the primary window's first.
+    pub fn iter_transitions(&self) -> impl Iterator<Item = PickingTransition> + '_ {
+        self.iter().flat_map(|x| x.iter_transitions())
+    }
+}
+
+impl Deref for PickingStateMachines {
+    type Target = EntityHashMap<PickingStateMachine>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for PickingStateMachines {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
 }
 
 impl PickingStateMachine {
@@ -169,9 +835,10 @@ impl PickingStateMachine {
                     EntityPickingState::None
                 }
             }
-            GlobalPickingState::Pressed { entity: e } => {
+            GlobalPickingState::Pressed { entity: e }
+            | GlobalPickingState::Dragging { entity: e } => {
                 if entity == e {
-                    EntityPickingState::Hover
+                    EntityPickingState::Pressed
                 } else {
                     EntityPickingState::None
                 }
@@ -185,20 +852,150 @@ impl PickingStateMachine {
             GlobalPickingState::None => EntityPickingState::None,
             GlobalPickingState::Hover { .. } => EntityPickingState::Hover,
             GlobalPickingState::Pressed { .. } => EntityPickingState::Pressed,
+            GlobalPickingState::Dragging { .. } => EntityPickingState::Pressed,
+        }
+    }
+
+    /// Returns a summary of the current interaction.
+    pub fn interaction_kind(&self) -> InteractionKind {
+        match self.current {
+            GlobalPickingState::None => InteractionKind::Idle,
+            GlobalPickingState::Hover { entity } => InteractionKind::Hovering(entity),
+            GlobalPickingState::Pressed { entity } => InteractionKind::Pressing(entity),
+            GlobalPickingState::Dragging { entity } => InteractionKind::Dragging(entity),
         }
     }
 
     /// Returns the current state transition events on an entity.
     pub fn iter_transitions(&self) -> impl Iterator<Item = PickingTransition> {
-        self.transitions.iter().copied()
+        self.transitions.iter().cloned()
+    }
+
+    /// Returns an owned copy of the current state transition events,
+    /// e.g. for sending to another thread, serializable with the `serde` feature.
+    pub fn collect_transitions(&self) -> Vec<PickingTransition> {
+        self.transitions.clone()
+    }
+
+    /// Returns the entity, pointer position and button of the most recent click,
+    /// i.e. [`PickingTransition::Clicked`], kept until the next click.
+    pub fn last_click(&self) -> Option<(Entity, Vec2, MouseButton)> {
+        self.last_release
+            .map(|(entity, _, position, button)| (entity, position, button))
+    }
+
+    /// Returns the button if `entity` is pressed this frame.
+    pub fn just_pressed(&self, entity: Entity) -> Option<MouseButton> {
+        self.get_transitions(entity).find_map(|x| match x {
+            PickingTransition::Pressed { button, .. } => Some(button),
+            _ => None,
+        })
+    }
+
+    /// Returns the button and whether the pointer is outside if `entity` is released this frame.
+    pub fn just_released(&self, entity: Entity) -> Option<(MouseButton, bool)> {
+        self.get_transitions(entity).find_map(|x| match x {
+            PickingTransition::Released {
+                button, outside, ..
+            } => Some((button, outside)),
+            _ => None,
+        })
+    }
+
+    /// Returns true if `entity` is hovered this frame.
+    pub fn just_hovered(&self, entity: Entity) -> bool {
+        self.get_transitions(entity)
+            .any(|x| matches!(x, PickingTransition::HoverEnter { .. }))
+    }
+
+    /// Returns true if `entity` stops being hovered this frame.
+    pub fn just_unhovered(&self, entity: Entity) -> bool {
+        self.get_transitions(entity)
+            .any(|x| matches!(x, PickingTransition::HoverExit { .. }))
+    }
+
+    /// Returns the entity and button of this frame's release inside the pressed entity, if any.
+    pub fn clicked_entity(&self) -> Option<(Entity, MouseButton)> {
+        self.transitions
+            .iter()
+            .find_map(PickingTransition::released_inside)
     }
 
     /// Returns the current state transition events on an entity.
     pub fn get_transitions(&self, entity: Entity) -> impl Iterator<Item = PickingTransition> {
         self.transitions
             .iter()
-            .copied()
             .filter(move |x| x.entity() == entity)
+            .cloned()
+    }
+
+    /// Returns the current state transition events of a button.
+    ///
+    /// If `include_hover` is true, transitions not tied to a button are also included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_picking_state_machine::{PickingStateMachine, PickingTransition};
+    /// let entity = Entity::PLACEHOLDER;
+    /// let mut state_machine = PickingStateMachine::default();
+    /// state_machine.transitions.extend([
+    ///     PickingTransition::HoverExit { entity },
+    ///     PickingTransition::Clicked { entity, button: MouseButton::Right, count: 1 },
+    /// ]);
+    /// assert_eq!(state_machine.button_transitions(MouseButton::Right, false).count(), 1);
+    /// assert_eq!(state_machine.button_transitions(MouseButton::Right, true).count(), 2);
+    /// assert_eq!(state_machine.button_transitions(MouseButton::Left, false).count(), 0);
+    /// ```
+    pub fn button_transitions(
+        &self,
+        button: MouseButton,
+        include_hover: bool,
+    ) -> impl Iterator<Item = PickingTransition> {
+        self.transitions
+            .iter()
+            .filter(move |x| match x.button() {
+                Some(b) => b == button,
+                None => include_hover,
+            })
+            .cloned()
+    }
+
+    /// Returns the current state transition events of the left mouse button.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_picking_state_machine::{PickingStateMachine, PickingTransition};
+    /// fn on_left_click(state_machine: Res<PickingStateMachine>) {
+    ///     for transition in state_machine.left_transitions(false) {
+    ///         if let PickingTransition::Clicked { entity, count, .. } = transition {
+    ///             println!("{entity} clicked {count} times");
+    ///         }
+    ///     }
+    /// }
+    /// # App::new().add_systems(Update, on_left_click);
+    /// ```
+    pub fn left_transitions(&self, include_hover: bool) -> impl Iterator<Item = PickingTransition> {
+        self.button_transitions(MouseButton::Left, include_hover)
+    }
+
+    /// Returns the current state transition events of the right mouse button.
+    pub fn right_transitions(
+        &self,
+        include_hover: bool,
+    ) -> impl Iterator<Item = PickingTransition> {
+        self.button_transitions(MouseButton::Right, include_hover)
+    }
+
+    /// Returns the current state transition events of the middle mouse button.
+    pub fn middle_transitions(
+        &self,
+        include_hover: bool,
+    ) -> impl Iterator<Item = PickingTransition> {
+        self.button_transitions(MouseButton::Middle, include_hover)
     }
 
     /// Returns the active entity that is being hovered or pressed.
@@ -236,9 +1033,58 @@ impl PickingStateMachine {
         matches!(self.current, GlobalPickingState::Hover { .. })
     }
 
+    /// Returns the button of the current press.
+    ///
+    /// `None` on the frame the button is released, see [`PickingStateMachine::tracked_just_released`].
+    pub fn tracked_button(&self) -> Option<MouseButton> {
+        self.press.map(|x| x.button)
+    }
+
+    /// Returns true if the tracked button is just pressed, same as `current_btn_just_pressed`.
+    pub fn tracked_just_pressed(&self) -> bool {
+        self.current_btn_just_pressed
+    }
+
+    /// Returns true if the mouse button of the previous frame's press is released this frame.
+    ///
+    /// This is the raw input edge, debounced releases and releases during cancellation
+    /// also count.
+    pub fn tracked_just_released(&self) -> bool {
+        self.tracked_just_released
+    }
+
     /// Returns true if a recognized button is pressed and not in cancellation state.
     pub fn is_pressing(&self) -> bool {
-        matches!(self.current, GlobalPickingState::Pressed { .. })
+        matches!(
+            self.current,
+            GlobalPickingState::Pressed { .. } | GlobalPickingState::Dragging { .. }
+        )
+    }
+
+    /// Returns true if the pressed entity is being dragged.
+    pub fn is_dragging(&self) -> bool {
+        matches!(self.current, GlobalPickingState::Dragging { .. })
+    }
+
+    /// Returns true if the pointer is over something pickable,
+    /// even if it cannot be hovered right now, e.g. while pressing something else.
+    pub fn pointer_over_interactive(&self) -> bool {
+        self.has_hits_this_frame
+    }
+
+    /// Returns true if the transitions of this frame are produced programmatically,
+    /// e.g. by [`PickingStateMachine::keyboard_press`].
+    pub fn was_synthetic(&self) -> bool {
+        self.synthetic
+    }
+
+    /// Returns true if nothing is happening: no button held, pointer still,
+    /// no transitions and not in cancellation state.
+    pub fn is_quiescent(&self) -> bool {
+        self.press.is_none()
+            && !self.pointer_moved
+            && self.transitions.is_empty()
+            && !self.is_post_cancellation_state
     }
 
     /// Returns true if in cancellation state.
@@ -246,6 +1092,185 @@ impl PickingStateMachine {
         self.is_post_cancellation_state
     }
 
+    /// Returns true if any allowed button is released or a press is cancelled this frame,
+    /// over any entity or none.
+    pub fn any_release(&self) -> bool {
+        self.button_released
+            || self.transitions.iter().any(|x| {
+                matches!(
+                    x,
+                    PickingTransition::Released { .. } | PickingTransition::Cancelled { .. }
+                )
+            })
+    }
+
+    /// Returns the pressed entity if the current press began on the entity
+    /// last released inside within `PickingStateMachinePlugin::multi_click_interval`.
+    ///
+    /// Useful for select-then-manipulate gestures like resize handles.
+    pub fn press_on_recent(&self) -> Option<Entity> {
+        self.recent_press
+    }
+
+    /// Returns the pointer movement since the previous frame.
+    ///
+    /// Zero while out of bounds and on the frame the pointer enters bounds.
+    pub fn pointer_delta(&self) -> Vec2 {
+        self.pointer_delta
+    }
+
+    /// Returns the pointer clamped to `rect`, in the same space as [`PickingStateMachine::pointer`].
+    ///
+    /// Pass the pressed entity's bounds to keep e.g. a slider's value in range
+    /// while the pointer is dragged outside of it.
+    pub fn clamped_pointer(&self, rect: Rect) -> Vec2 {
+        self.pointer.max(rect.min).min(rect.max)
+    }
+
+    /// Returns the offset of the pointer from the press position, if pressed by a pointer.
+    pub fn drag_offset(&self) -> Option<Vec2> {
+        self.button_press().map(|x| self.pointer - x.position)
+    }
+
+    /// Returns the axis the current drag is locked to.
+    ///
+    /// Decided once [`PickingStateMachine::drag_offset`] exceeds
+    /// `threshold` pixels on its dominant axis, and kept until the press ends.
+    pub fn locked_drag_axis(&self, threshold: f32) -> Option<DragAxis> {
+        self.drag_axis
+            .iter()
+            .find(|(max, _)| *max > threshold)
+            .and_then(|(_, axis)| *axis)
+    }
+
+    /// Returns [`PickingStateMachine::drag_offset`] snapped to `grid`.
+    ///
+    /// `origin` is the starting position of the dragged item in grid space,
+    /// the returned offset moves it onto the nearest grid point.
+    /// The total offset is snapped so there is no drift.
+    /// Axes with zero grid size are not snapped.
+    pub fn snapped_drag_offset(&self, grid: Vec2, origin: Vec2) -> Option<Vec2> {
+        let snap = |offset: f32, grid: f32, origin: f32| {
+            if grid == 0.0 {
+                offset
+            } else {
+                ((origin + offset) / grid).round() * grid - origin
+            }
+        };
+        let offset = self.drag_offset()?;
+        Some(Vec2::new(
+            snap(offset.x, grid.x, origin.x),
+            snap(offset.y, grid.y, origin.y),
+        ))
+    }
+
+    /// Returns true if pressing `button` on `entity` would press it instead of only hovering it.
+    ///
+    /// Presses are decided in this order:
+    /// * Nothing is pressed until all buttons are released after a cancellation.
+    /// * A button must be in `PickingStateMachinePlugin::allowed_buttons`,
+    ///   as of the last resolve, to be tracked at all.
+    /// * The entity's [`ButtonFilter`] decides between press and hover,
+    ///   entities without a filter allow all buttons.
+    ///
+    /// [`ExtraButtons`] and [`DefaultButtonFilter`] are not considered,
+    /// see [`ButtonFilters::press_allowed`] for the decision including them.
+    pub fn would_press(
+        &self,
+        entity: Entity,
+        button: MouseButton,
+        filters: &Query<&ButtonFilter>,
+    ) -> bool {
+        !self.is_post_cancellation_state
+            && self.allowed_buttons.contains(&button)
+            && filters.get(entity).ok().is_none_or(|x| x.contains(button))
+    }
+
+    /// Returns true if a [`ToggleButton`] entity is toggled on.
+    pub fn is_toggled(&self, entity: Entity) -> bool {
+        self.toggled.contains(&entity)
+    }
+
+    /// Returns the entity and reason if a press ended this frame.
+    pub fn press_ended(&self) -> Option<PressEnd> {
+        // A despawned pressed entity is also cancelled, report the despawn instead.
+        let lost = self
+            .iter_transitions()
+            .find(|x| matches!(x, PickingTransition::Lost { pressed: true, .. }));
+        if let Some(x) = lost {
+            return Some(PressEnd {
+                entity: x.entity(),
+                reason: EndReason::LostTarget,
+            });
+        }
+        self.iter_transitions().find_map(|x| {
+            let reason = match x {
+                PickingTransition::Released { outside: false, .. } => EndReason::ReleasedInside,
+                PickingTransition::Released { outside: true, .. } => EndReason::ReleasedOutside,
+                PickingTransition::Cancelled { .. } => EndReason::Cancelled,
+                _ => return None,
+            };
+            Some(PressEnd {
+                entity: x.entity(),
+                reason,
+            })
+        })
+    }
+
+    /// Returns seconds since the last transition or pointer movement,
+    /// given `now` as the elapsed seconds of [`Time<Virtual>`].
+    ///
+    /// Like [`PickingStateMachine::now`], time spent with `timers_paused` set is excluded.
+    pub fn idle_time(&self, now: f32) -> f32 {
+        (self.clock(now) - self.last_activity).max(0.0)
+    }
+
+    /// Converts elapsed seconds of [`Time<Virtual>`] to [`PickingStateMachine::now`].
+    fn clock(&self, elapsed: f32) -> f32 {
+        if self.timers_paused {
+            self.now
+        } else {
+            elapsed - self.paused_time
+        }
+    }
+
+    /// Returns the entity that would be hovered if nothing was pressed or gated,
+    /// i.e. the topmost entity under the pointer this frame.
+    ///
+    /// Useful to peek at the drop target while dragging.
+    pub fn shadow_hover(&self) -> Option<Entity> {
+        self.shadow_hover
+    }
+
+    /// Returns the topmost entity under the pointer while dragging,
+    /// which may be the dragged entity itself.
+    ///
+    /// Changes emit [`PickingTransition::DragEnter`].
+    pub fn drag_over(&self) -> Option<Entity> {
+        self.drag_over
+    }
+
+    /// Returns the fraction of `duration` that `entity` has been continuously pressed,
+    /// clamped to `1.0`, or `None` if not pressed.
+    ///
+    /// Useful for hold to confirm buttons.
+    pub fn hold_progress(&self, entity: Entity, duration: f32) -> Option<f32> {
+        if !self.is_pressing() || self.get_active_entity() != Some(entity) {
+            return None;
+        }
+        if duration <= 0.0 {
+            return Some(1.0);
+        }
+        Some(((self.now - self.pressed_since) / duration).clamp(0.0, 1.0))
+    }
+
+    /// Returns seconds the current entity has been hovered, `None` if not hovering,
+    /// given `now` as the elapsed seconds of [`Time<Virtual>`].
+    pub fn hover_elapsed(&self, now: f32) -> Option<f32> {
+        self.hover_started
+            .map(|started| (self.clock(now) - started).max(0.0))
+    }
+
     /// We allow acquiring new target if
     /// * Not post-cancellation state.
     /// * Not pressed or just pressed.
@@ -253,6 +1278,26 @@ impl PickingStateMachine {
         !self.is_post_cancellation_state && (self.press.is_none() || self.current_btn_just_pressed)
     }
 
+    /// Returns true if new entities cannot be hovered or pressed right now,
+    /// see [`PickingStateMachine::gate_reason`].
+    ///
+    /// Does not account for `PickingStateMachinePlugin::acquire_while_pressed`.
+    pub fn acquisition_gated(&self) -> bool {
+        !self.can_acquire_new_target()
+    }
+
+    /// Returns why new entities cannot be hovered or pressed right now,
+    /// e.g. to tell users to release other buttons.
+    pub fn gate_reason(&self) -> Option<GateReason> {
+        if self.is_post_cancellation_state {
+            Some(GateReason::PostCancellation)
+        } else if self.press.is_some() && !self.current_btn_just_pressed {
+            Some(GateReason::Pressed)
+        } else {
+            None
+        }
+    }
+
     /// Hover over an entity with a non-mouse action.
     pub fn keyboard_hover(&mut self, entity: Entity) {
         self.owner = CursorOwner::Keyboard;
@@ -267,155 +1312,756 @@ impl PickingStateMachine {
 
     /// Press the active entity with a non-mouse action.
     pub fn keyboard_press(&mut self) {
+        self.scripted = true;
+        self.virtual_press(PointerSource::Programmatic);
+    }
+
+    /// Press like [`PickingStateMachine::keyboard_press`] without marking the frame synthetic.
+    pub(crate) fn virtual_press(&mut self, source: PointerSource) {
         self.owner = CursorOwner::Keyboard;
         if let GlobalPickingState::Hover { entity } = self.current {
+            self.press_travel = 0.0;
             self.current = GlobalPickingState::Pressed { entity };
             self.press = Some(PressState {
-                button: MouseButton::Other(u16::MAX),
+                button: VIRTUAL_BUTTON,
                 position: Vec2::ZERO,
                 time: self.now,
+                source,
+                modifiers: Modifiers::NONE,
             })
         }
     }
 
+    /// Returns the press if it's made by a button, i.e. not by [`PickingStateMachine::keyboard_press`]
+    /// or `gamepad_confirm`.
+    fn button_press(&self) -> Option<PressState> {
+        self.press.filter(|x| x.button != VIRTUAL_BUTTON)
+    }
+
     /// Release the active entity with a non-mouse action.
     pub fn keyboard_release(&mut self) {
+        self.scripted = true;
+        self.virtual_release();
+    }
+
+    /// Release like [`PickingStateMachine::keyboard_release`] without marking the frame synthetic.
+    pub(crate) fn virtual_release(&mut self) {
         self.owner = CursorOwner::Keyboard;
         if let GlobalPickingState::Pressed { entity } = self.current {
+            // `press` is kept until the transitions are queued.
             self.current = GlobalPickingState::Hover { entity };
-            self.press = None
         }
     }
+
+    /// Release the pressed or dragged entity at the current pointer on the next resolve,
+    /// as if the button is released, e.g. to drop with a keyboard shortcut.
+    ///
+    /// Like a real release, the release is outside and followed by `HoverExit`
+    /// if the entity is not under the pointer.
+    ///
+    /// The held button is then ignored until released.
+    pub fn commit_drag(&mut self) {
+        if self.is_pressing() {
+            self.drag_end = Some(true);
+        }
+    }
+
+    /// Cancel the pressed or dragged entity on the next resolve, emitting `Cancelled`.
+    ///
+    /// The held button is then ignored until released.
+    pub fn abort_drag(&mut self) {
+        if self.is_pressing() {
+            self.drag_end = Some(false);
+        }
+    }
+
+    /// Enter cancellation state as if multiple buttons are pressed, e.g. when opening a modal.
+    ///
+    /// The next resolve emits `Cancelled` on the pressed entity,
+    /// and no new entity is hovered or pressed until all buttons are released.
+    /// Hover is kept unless `PickingStateMachinePlugin::cancel_hover` is set.
+    ///
+    /// Safe to call from any system with [`ResMut<PickingStateMachine>`](ResMut).
+    pub fn request_cancel(&mut self) {
+        self.is_post_cancellation_state = true;
+        self.cancel_requested = true;
+    }
 }
 
 fn picking_window_system(
+    time: Res<Time<Virtual>>,
+    settings: Res<PickingStateMachinePlugin>,
     mut state_machine: ResMut<PickingStateMachine>,
-    window: Query<&Window, With<PrimaryWindow>>,
+    mut machines: ResMut<PickingStateMachines>,
+    windows: Query<(Entity, &Window, Has<PrimaryWindow>)>,
+    touches: Option<Res<Touches>>,
+    input_override: Option<Res<PickingInputOverride>>,
+) {
+    // The primary window's machine is the `PickingStateMachine` resource.
+    machines.retain(|entity, _| windows.get(*entity).is_ok_and(|(.., primary)| !primary));
+    for (entity, _, primary) in &windows {
+        if !primary {
+            machines.entry(entity).or_default();
+        }
+    }
+    // Overlapping background windows may still report the cursor.
+    let usable = |window: &Window| !settings.respect_window_focus || window.focused;
+    let cursor = |entity: Entity| {
+        windows
+            .get(entity)
+            .ok()
+            .and_then(|(_, window, _)| window.cursor_position().filter(|_| usable(window)))
+    };
+    // Prefer the cursor, otherwise use the oldest finger on the primary window.
+    let touch = touches
+        .as_deref()
+        .and_then(|touches| touches.iter().min_by_key(|touch| touch.id()))
+        .map(|touch| touch.position());
+    let primary = windows
+        .iter()
+        .find(|(_, window, primary)| *primary && usable(window))
+        .map(|(entity, window, _)| (entity, window));
+    let found = primary.and_then(|(entity, window)| match (cursor(entity), touch) {
+        (Some(position), _) => Some((entity, window, position, PointerSource::Mouse)),
+        (None, Some(position)) => Some((entity, window, position, PointerSource::Touch)),
+        (None, None) => None,
+    });
+    // Overridden positions are already relative to `pointer_origin`.
+    let overridden = input_override.as_deref().and_then(|x| x.pointer);
+    update_pointer(&mut state_machine, &settings, &time, found, overridden);
+    for (entity, state_machine) in machines.iter_mut() {
+        let found = windows.get(*entity).ok().and_then(|(entity, window, _)| {
+            cursor(entity).map(|position| (entity, window, position, PointerSource::Mouse))
+        });
+        update_pointer(state_machine, &settings, &time, found, None);
+    }
+}
+
+/// Update the pointer of a machine from the position `found` in a window.
+fn update_pointer(
+    state_machine: &mut PickingStateMachine,
+    settings: &PickingStateMachinePlugin,
+    time: &Time<Virtual>,
+    found: Option<(Entity, &Window, Vec2, PointerSource)>,
+    overridden: Option<Vec2>,
 ) {
-    let mouse_position = match window.single() {
-        Ok(window) => window.cursor_position(),
-        Err(_) => None,
+    if state_machine.timers_paused {
+        state_machine.paused_time += time.delta_secs();
+    } else {
+        state_machine.now = time.elapsed_secs() - state_machine.paused_time;
+    }
+    state_machine.window = found.map(|(entity, ..)| entity);
+    let (mouse_position, source) = match found {
+        Some((_, window, position, source)) => {
+            let position = match settings.pointer_origin {
+                PointerOrigin::TopLeft => position,
+                PointerOrigin::BottomLeft => Vec2::new(position.x, window.height() - position.y),
+            };
+            (Some(position), Some(source))
+        }
+        None => (None, None),
+    };
+    let (mouse_position, source) = match overridden {
+        Some(position) => (Some(position), Some(PointerSource::Programmatic)),
+        None => (mouse_position, source),
+    };
+    // The first position has nothing to be compared to.
+    let had_pointer =
+        state_machine.pointer_source.is_some() && !state_machine.pointer_is_out_of_bounds;
+    state_machine.pointer_source = source;
+    state_machine.pointer_moved = match mouse_position {
+        Some(position) => {
+            state_machine.pointer_is_out_of_bounds || state_machine.pointer != position
+        }
+        None => !state_machine.pointer_is_out_of_bounds,
     };
     match mouse_position {
         Some(position) => {
+            if state_machine.pointer != position {
+                state_machine.last_activity = state_machine.now;
+            }
+            let dt = time.delta_secs();
+            state_machine.pointer_velocity = if state_machine.pointer_is_out_of_bounds || dt <= 0.0
+            {
+                Vec2::ZERO
+            } else {
+                (position - state_machine.pointer) / dt
+            };
+            state_machine.pointer_delta = if had_pointer {
+                position - state_machine.pointer
+            } else {
+                Vec2::ZERO
+            };
+            state_machine.previous_pointer = if had_pointer {
+                state_machine.pointer
+            } else {
+                position
+            };
             state_machine.pointer = position;
+            state_machine.pointer_predicted =
+                position + state_machine.pointer_velocity * settings.prediction_time;
             state_machine.pointer_is_out_of_bounds = false;
         }
         None => {
+            state_machine.pointer_delta = Vec2::ZERO;
+            state_machine.previous_pointer = state_machine.pointer;
+            state_machine.pointer_velocity = Vec2::ZERO;
+            state_machine.pointer_predicted = state_machine.pointer;
             state_machine.pointer_is_out_of_bounds = true;
         }
     }
 }
 
 fn picking_button_system(
-    time: Res<Time<Virtual>>,
     mut state_machine: ResMut<PickingStateMachine>,
+    mut machines: ResMut<PickingStateMachines>,
     settings: Res<PickingStateMachinePlugin>,
     input: Res<ButtonInput<MouseButton>>,
     mut mouse_movements: MessageReader<MouseMotion>,
-) -> bool {
+    extra_buttons: Query<&'static ExtraButtons>,
+    commit: Option<Res<PressCommit>>,
+    input_override: Option<Res<PickingInputOverride>>,
+    mut last_override: Local<Vec<MouseButton>>,
+    gamepads: Query<&'static Gamepad>,
+    keys: Res<ButtonInput<KeyCode>>,
+) -> Vec<bool> {
+    let moved = mouse_movements.read().count() > 0;
+    let overridden = input_override.as_deref().and_then(|x| x.buttons.as_ref());
+    let inputs = ButtonInputs {
+        settings: &settings,
+        settings_changed: settings.is_changed(),
+        input: &input,
+        moved,
+        extra_buttons: &extra_buttons,
+        commit: commit.as_deref(),
+        overridden,
+        last_override: &last_override,
+        gamepads: &gamepads,
+        keys: &keys,
+    };
+    // Buttons are shared by all windows, in the order of `picking_state_machine_system`.
+    let pressed = iter::once(&mut *state_machine)
+        .chain(machines.values_mut())
+        .map(|state_machine| update_buttons(state_machine, &inputs))
+        .collect();
+    last_override.clone_from(overridden.unwrap_or(&Vec::new()));
+    pressed
+}
+
+/// Inputs of [`picking_button_system`] shared by all machines.
+struct ButtonInputs<'a, 'w, 's> {
+    settings: &'a PickingStateMachinePlugin,
+    settings_changed: bool,
+    input: &'a ButtonInput<MouseButton>,
+    moved: bool,
+    extra_buttons: &'a Query<'w, 's, &'static ExtraButtons>,
+    commit: Option<&'a PressCommit>,
+    overridden: Option<&'a Vec<MouseButton>>,
+    last_override: &'a [MouseButton],
+    gamepads: &'a Query<'w, 's, &'static Gamepad>,
+    keys: &'a ButtonInput<KeyCode>,
+}
+
+/// Update the press of a machine, returns true if the tracked button is held.
+fn update_buttons(state_machine: &mut PickingStateMachine, inputs: &ButtonInputs) -> bool {
+    let ButtonInputs {
+        settings,
+        settings_changed,
+        input,
+        moved,
+        extra_buttons,
+        commit,
+        overridden,
+        last_override,
+        gamepads,
+        keys,
+    } = *inputs;
     let mut current_button = None;
     let mut cancel = false;
+    let mut chord = SmallVec::new();
     let mut just_pressed = false;
-    let time = time.elapsed_secs();
-    state_machine.now = time;
-    for button in &settings.allowed_buttons {
-        if input.pressed(*button) {
-            if input.just_pressed(*button) {
-                just_pressed = true;
-            }
-            if current_button.is_none() {
-                current_button = Some(*button)
-            } else {
-                current_button = None;
+    let time = state_machine.now;
+    if state_machine.allowed_buttons != settings.allowed_buttons {
+        state_machine
+            .allowed_buttons
+            .clone_from(&settings.allowed_buttons);
+    }
+    // Extra buttons of the active entity are temporarily allowed.
+    let extra = state_machine
+        .get_active_entity()
+        .and_then(|entity| extra_buttons.get(entity).ok());
+    // A press of a button no longer allowed is cancelled, hovers are unaffected.
+    if settings_changed {
+        if let Some(press) = state_machine.button_press() {
+            if !settings.allowed_buttons.contains(&press.button)
+                && !extra.is_some_and(|x| x.contains(press.button))
+            {
                 cancel = true;
-                break;
             }
         }
     }
-    if just_pressed || mouse_movements.read().count() > 0 {
+    let held = |button| match overridden {
+        Some(buttons) => buttons.contains(&button),
+        None => input.pressed(button),
+    };
+    let newly_held = |button| match overridden {
+        Some(buttons) => buttons.contains(&button) && !last_override.contains(&button),
+        None => input.just_pressed(button),
+    };
+    // An extra button held before reaching the entity stays disallowed until released,
+    // otherwise it would alternate between pressing and hovering each frame.
+    let pressed_button = state_machine.button_press().map(|x| x.button);
+    let allowed = || {
+        settings.allowed_buttons.iter().copied().chain(
+            extra
+                .into_iter()
+                .flat_map(|x| x.iter())
+                .filter(|b| !settings.allowed_buttons.contains(b))
+                .filter(move |b| newly_held(*b) || pressed_button == Some(*b)),
+        )
+    };
+    let newly_released = |button| match overridden {
+        Some(buttons) => !buttons.contains(&button) && last_override.contains(&button),
+        None => input.just_released(button),
+    };
+    #[cfg(feature = "debug")]
+    {
+        state_machine.button_went_down = allowed().any(newly_held);
+    }
+    // Raw edge of the tracked button, before debouncing and cancellation.
+    state_machine.tracked_just_released = state_machine
+        .button_press()
+        .is_some_and(|x| newly_released(x.button));
+    match settings.multi_button_policy {
+        MultiButtonPolicy::Cancel => {
+            let buttons: SmallVec<[MouseButton; 2]> = allowed().filter(|b| held(*b)).collect();
+            just_pressed = buttons.iter().any(|b| newly_held(*b));
+            match buttons.as_slice() {
+                [] => (),
+                [button] => current_button = Some(*button),
+                _ => {
+                    chord = buttons;
+                    cancel = true;
+                }
+            }
+        }
+        MultiButtonPolicy::Priority => {
+            let tracked = state_machine.button_press().map(|x| x.button);
+            let mut allowed = allowed().filter(|b| held(*b));
+            match tracked {
+                // The tracked button wins while held.
+                Some(button) if held(button) => current_button = Some(button),
+                // Release first, the next held button is pressed on the next frame.
+                Some(_) => state_machine.promote_pending = allowed.next().is_some(),
+                None => {
+                    current_button = allowed.next();
+                    let promoted = core::mem::take(&mut state_machine.promote_pending);
+                    just_pressed = current_button.is_some_and(|b| promoted || newly_held(b));
+                }
+            }
+        }
+        MultiButtonPolicy::FirstWins => {
+            let tracked = state_machine.button_press().map(|x| x.button);
+            let mut allowed = allowed().filter(|b| held(*b));
+            match tracked {
+                Some(button) if held(button) => current_button = Some(button),
+                // Hand the press over to a button still held.
+                Some(from) => {
+                    current_button = allowed.next();
+                    if let Some(to) =
+                        current_button.filter(|_| !state_machine.is_post_cancellation_state)
+                    {
+                        state_machine.tracking_switched = Some((from, to));
+                        if let Some(press) = &mut state_machine.press {
+                            press.button = to;
+                        }
+                    }
+                }
+                None => {
+                    current_button = allowed.next();
+                    just_pressed = current_button.is_some_and(newly_held);
+                }
+            }
+        }
+    }
+    if just_pressed || moved {
         state_machine.owner = CursorOwner::Mouse;
     }
+    // The confirm button presses the hovered entity like `keyboard_press`.
+    if let Some(confirm) = settings.gamepad_confirm {
+        if gamepads.iter().any(|x| x.just_pressed(confirm)) {
+            if state_machine.is_hovering() && state_machine.press.is_none() {
+                state_machine.virtual_press(PointerSource::Gamepad);
+            }
+        } else if gamepads.iter().any(|x| x.just_released(confirm))
+            && state_machine
+                .press
+                .is_some_and(|x| x.source == PointerSource::Gamepad)
+        {
+            state_machine.virtual_release();
+        }
+    }
+    if core::mem::take(&mut state_machine.cancel_requested) {
+        cancel = true;
+    }
     // To make state transitions less weird,
     // if you release one button and press another in the same frame,
     // treat it as entering cancellation state,
     // this ensures one event per frame.
     if let Some(press) = state_machine.press {
-        if current_button.is_some_and(|b| b != press.button) {
+        if let Some(button) = current_button.filter(|b| *b != press.button) {
+            chord = SmallVec::from_slice(&[press.button, button]);
             cancel = true;
         }
     }
+    // Bridge a brief release of the tracked button if it's pressed again in time.
+    if settings.release_debounce > 0.0 && !cancel && !state_machine.is_post_cancellation_state {
+        if let Some(press) = state_machine.button_press() {
+            match current_button {
+                None => {
+                    let pointer = state_machine.pointer;
+                    let (since, position) =
+                        *state_machine.release_pending.get_or_insert((time, pointer));
+                    if time - since < settings.release_debounce
+                        && pointer.distance(position) <= settings.release_debounce_radius
+                    {
+                        current_button = Some(press.button);
+                    } else {
+                        state_machine.release_pending = None;
+                    }
+                }
+                Some(button) if button == press.button => {
+                    if state_machine.release_pending.take().is_some() {
+                        just_pressed = false;
+                    }
+                }
+                Some(_) => state_machine.release_pending = None,
+            }
+        }
+    }
+    // A new press only commits once the hook accepts it.
+    if let (Some(commit), Some(button), None) = (commit, current_button, state_machine.press) {
+        if !cancel && !state_machine.is_post_cancellation_state {
+            if (commit.0)(button, state_machine.pointer) {
+                just_pressed = true;
+            } else {
+                current_button = None;
+                just_pressed = false;
+            }
+        }
+    }
     state_machine.current_btn_just_pressed = false;
     if cancel {
+        if !state_machine.is_post_cancellation_state {
+            // Report the tracked button first.
+            let tracked = state_machine.button_press().map(|x| x.button);
+            if let Some(i) = chord.iter().position(|b| Some(*b) == tracked) {
+                chord[..=i].rotate_right(1);
+            }
+            state_machine.resume_button = tracked.filter(|_| chord.len() > 1);
+            state_machine.chord = chord;
+        }
         state_machine.is_post_cancellation_state = true;
     } else if state_machine.is_post_cancellation_state && current_button.is_none() {
         state_machine.is_post_cancellation_state = false;
+        state_machine.chord.clear();
+        state_machine.resume_button = None;
+    } else if settings.resume_after_chord
+        && state_machine.is_post_cancellation_state
+        && current_button.is_some()
+        && current_button == state_machine.resume_button
+    {
+        // Only the button of the cancelled press is held, resume it as a new press.
+        state_machine.is_post_cancellation_state = false;
+        state_machine.chord.clear();
+        state_machine.resume_button = None;
+        state_machine.current_btn_just_pressed = true;
     } else if just_pressed {
         state_machine.current_btn_just_pressed = true;
     }
     // We need to keep this for events so deletion is delayed.
     if let Some(button) = current_button {
-        state_machine.press = Some(PressState {
-            button,
-            position: state_machine.pointer,
-            time,
-        });
+        // Keep the position and time of the initial press.
+        if state_machine.current_btn_just_pressed
+            || state_machine.press.is_none_or(|x| x.button != button)
+        {
+            state_machine.press_travel = 0.0;
+            state_machine.press = Some(PressState {
+                button,
+                position: state_machine.pointer,
+                time,
+                source: state_machine.pointer_source.unwrap_or_default(),
+                modifiers: Modifiers::from_input(keys),
+            });
+        } else {
+            // `pointer_delta` is zero while out of bounds.
+            let delta = state_machine.pointer_delta.length();
+            state_machine.press_travel += delta;
+        }
     }
     current_button.is_some()
 }
 
+fn route_scroll_enabled(settings: Res<PickingStateMachinePlugin>) -> bool {
+    settings.route_scroll
+}
+
+/// Accumulates the frame's wheel movements into one [`PickingTransition::Scrolled`].
+fn picking_scroll_system(
+    settings: Res<PickingStateMachinePlugin>,
+    mut wheel: MessageReader<MouseWheel>,
+    mut state_machine: ResMut<PickingStateMachine>,
+) {
+    let delta = wheel
+        .read()
+        .map(|x| match x.unit {
+            MouseScrollUnit::Line => Vec2::new(x.x, x.y) * settings.line_to_pixel,
+            MouseScrollUnit::Pixel => Vec2::new(x.x, x.y),
+        })
+        .sum::<Vec2>();
+    if delta == Vec2::ZERO {
+        return;
+    }
+    if let Some(entity) = state_machine.get_active_entity() {
+        state_machine
+            .transitions
+            .push(PickingTransition::Scrolled { entity, delta });
+    }
+}
+
 fn picking_state_machine_system(
-    pressed: In<bool>,
-    time: Res<Time<Virtual>>,
+    pressed: In<Vec<bool>>,
     settings: Res<PickingStateMachinePlugin>,
     mut pick: MessageReader<PointerHits>,
     mut state_machine: ResMut<PickingStateMachine>,
-    filters: Query<&ButtonFilter>,
-    priorities: Query<&PickPriority>,
+    mut machines: ResMut<PickingStateMachines>,
+    render_targets: Query<&RenderTarget>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    button_filters: ButtonFilters,
+    priorities: Query<(Option<&PickPriority>, Option<&PickTier>)>,
+    entities: &Entities,
+    toggles: Query<(), With<ToggleButton>>,
+    spawned: Query<(), Spawned>,
+    selectables: Query<(), With<Selectable>>,
+    mut selection: Option<ResMut<SelectionState>>,
+    multi_click_configs: Query<&MultiClickConfig>,
 ) {
+    let primary = primary_window.single().ok();
+    // Cameras not rendering to a window report to the primary window.
+    let window_of = |camera: Entity| match render_targets
+        .get(camera)
+        .ok()
+        .and_then(|x| x.normalize(primary))
+    {
+        Some(NormalizedRenderTarget::Window(window)) => {
+            Some(window.entity()).filter(|x| Some(*x) != primary)
+        }
+        _ => None,
+    };
+    let hits: Vec<_> = pick.read().collect();
+    let machines = iter::once((None, &mut *state_machine))
+        .chain(machines.iter_mut().map(|(entity, x)| (Some(*entity), x)));
+    for ((window, state_machine), pressed) in machines.zip(pressed.0) {
+        resolve_state_machine(
+            state_machine,
+            pressed,
+            &hits,
+            |camera| window_of(camera) == window,
+            &settings,
+            &button_filters,
+            &priorities,
+            entities,
+            &toggles,
+            &spawned,
+            &selectables,
+            selection.as_deref_mut(),
+            &multi_click_configs,
+        );
+    }
+}
+
+/// Resolve the state of a machine from the hits of its window.
+fn resolve_state_machine(
+    state_machine: &mut PickingStateMachine,
+    pressed: bool,
+    hits: &[&PointerHits],
+    in_window: impl Fn(Entity) -> bool,
+    settings: &PickingStateMachinePlugin,
+    button_filters: &ButtonFilters,
+    priorities: &Query<(Option<&PickPriority>, Option<&PickTier>)>,
+    entities: &Entities,
+    toggles: &Query<(), With<ToggleButton>>,
+    spawned: &Query<(), Spawned>,
+    selectables: &Query<(), With<Selectable>>,
+    selection: Option<&mut SelectionState>,
+    multi_click_configs: &Query<&MultiClickConfig>,
+) {
+    // Per frame flags must not leak from a previous frame through the early returns below.
+    let tracking_switched = state_machine.tracking_switched.take();
+    state_machine.button_released = false;
+    // End a press by `commit_drag` like a release at the pointer, or `abort_drag` like a cancellation,
+    // either way the held button is then locked until released.
+    let drag_end = state_machine
+        .drag_end
+        .take()
+        .filter(|_| state_machine.is_pressing());
+    if drag_end == Some(false) {
+        state_machine.is_post_cancellation_state = true;
+    }
     // This is fine since this will be reset if the cursor moved or a button is pressed.
-    if state_machine.owner == CursorOwner::Keyboard {
+    if state_machine.owner == CursorOwner::Keyboard && drag_end.is_none() {
+        // Queue changes made by the `keyboard_*` methods as synthetic transitions.
+        state_machine.previous = state_machine.resolved;
+        let now = state_machine.now;
+        state_machine.queue_transitions(now, settings);
+        // `keyboard_*` methods may have been called on entities despawned since.
+        state_machine
+            .transitions
+            .retain(|x| entities.contains(x.entity()));
+        if let Some(entity) = state_machine
+            .get_active_entity()
+            .filter(|entity| settings.despawn_resets && !entities.contains(*entity))
+        {
+            let pressed = state_machine.is_pressing();
+            state_machine.current = GlobalPickingState::None;
+            state_machine.insert_lost(entity, pressed, now);
+        }
+        state_machine.synthetic = core::mem::take(&mut state_machine.scripted);
+        state_machine.has_hits_this_frame = false;
+        state_machine.shadow_hover = None;
+        state_machine.resolved = state_machine.current;
+        if !state_machine.is_pressing() {
+            state_machine.press = None;
+        }
+        return;
+    }
+    state_machine.scripted = false;
+    state_machine.synthetic = drag_end.is_some();
+    // If the active entity is despawned, drop it without emitting transitions on it.
+    let lost = state_machine
+        .get_active_entity()
+        .filter(|entity| settings.despawn_resets && !entities.contains(*entity))
+        .map(|entity| (entity, state_machine.is_pressing()));
+    if lost.is_some() {
+        state_machine.current = GlobalPickingState::None;
+    }
+    let pressed = pressed && drag_end != Some(true);
+    let time = state_machine.now;
+    state_machine.button_released =
+        !pressed && state_machine.press.is_some() && !state_machine.is_post_cancellation_state;
+    // Only hover acquisition is skipped, presses and releases are always resolved.
+    // The hits of the last resolve are kept until the next one.
+    let throttled = settings.resolve_interval > 0.0
+        && time - state_machine.last_resolve < settings.resolve_interval;
+    let quiescent = settings.skip_when_quiescent && state_machine.is_quiescent();
+    if (throttled || quiescent) && !pressed && state_machine.press.is_none() && lost.is_none() {
+        state_machine.previous = state_machine.current;
+        state_machine.transitions.clear();
         return;
     }
-    let pressed = *pressed;
-    let time = time.elapsed_secs();
-    let mut min = (f32::NEG_INFINITY, Reverse(f32::INFINITY));
+    state_machine.last_resolve = time;
+    let mut min = (0, f32::NEG_INFINITY, Reverse(f32::INFINITY));
     let mut target = None;
+    let mut target_hit = None;
+    let mut has_hits = false;
     let current = match state_machine.current {
         GlobalPickingState::None => None,
         GlobalPickingState::Hover { .. } => None,
+        GlobalPickingState::Pressed { .. } if settings.acquire_while_pressed => None,
         GlobalPickingState::Pressed { entity } => Some(entity),
+        GlobalPickingState::Dragging { entity } => Some(entity),
     };
-    let can_acquire = state_machine.can_acquire_new_target();
-    'main: for hits in pick.read() {
-        for (entity, hit) in &hits.picks {
-            if Some(*entity) == current {
-                target = current;
-                break 'main;
-            }
-            if !can_acquire {
+    // A press retained outside of its entity is still sticky.
+    let current = current.or(state_machine.retained);
+    let hovered = match state_machine.current {
+        GlobalPickingState::Hover { entity } => Some(entity),
+        _ => None,
+    };
+    // Drags stay on their entity even if presses follow the pointer.
+    let can_acquire = state_machine.can_acquire_new_target()
+        || (settings.acquire_while_pressed
+            && !state_machine.is_post_cancellation_state
+            && !state_machine.is_dragging()
+            && !state_machine.retained_dragging);
+    // A held button that pressed nothing may still press an entity that just appeared.
+    let acquire_spawned = settings.press_acquire_on_spawn
+        && state_machine.press.is_some()
+        && current.is_none()
+        && !state_machine.is_post_cancellation_state;
+    let max_hit_batches = settings.max_hit_batches.unwrap_or(usize::MAX);
+    let mut shadow_min = min;
+    let mut shadow = None;
+    let mut sticky = false;
+    let batches = hits
+        .iter()
+        .filter(|x| x.picks.iter().any(|(_, hit)| in_window(hit.camera)));
+    for hits in batches.take(max_hit_batches) {
+        for (entity, hit) in hits.picks.iter().filter(|(_, hit)| in_window(hit.camera)) {
+            // Backends may report entities despawned later in the frame.
+            if !entities.contains(*entity) {
                 continue;
             }
-            let priority = if let Ok(priority) = priorities.get(*entity) {
+            has_hits = true;
+            let (priority, tier) = priorities.get(*entity).unwrap_or_default();
+            // Higher tiers win regardless of order or depth.
+            let priority = if let Some(priority) = priority {
                 (
+                    tier.map_or(0, |x| x.0),
                     hits.order + priority.order,
                     Reverse(hit.depth - priority.distance),
                 )
             } else {
-                (hits.order, Reverse(hit.depth))
+                (tier.map_or(0, |x| x.0), hits.order, Reverse(hit.depth))
+            };
+            // The topmost hit regardless of presses and gating, for `shadow_hover`.
+            let shadows = match settings.within_batch_tiebreak {
+                HitTiebreak::FirstHit => priority > shadow_min,
+                HitTiebreak::LastHit => priority >= shadow_min,
+            };
+            if shadows {
+                shadow_min = priority;
+                shadow = Some(*entity);
+            }
+            if sticky {
+                continue;
+            }
+            if Some(*entity) == current {
+                target = current;
+                target_hit = Some(hit);
+                sticky = true;
+                continue;
+            }
+            let may_acquire = can_acquire || (acquire_spawned && spawned.contains(*entity));
+            if !may_acquire {
+                continue;
+            }
+            let wins = if settings.prefer_current_on_tie
+                && priority == min
+                && hovered.is_some()
+                && (target == hovered || Some(*entity) == hovered)
+            {
+                Some(*entity) == hovered
+            } else {
+                match settings.within_batch_tiebreak {
+                    HitTiebreak::FirstHit => priority > min,
+                    HitTiebreak::LastHit => priority >= min,
+                }
             };
-            if priority > min {
+            if wins {
                 min = priority;
                 target = Some(*entity);
+                target_hit = Some(hit);
             }
         }
     }
+    state_machine.has_hits_this_frame = has_hits;
+    state_machine.shadow_hover = shadow;
     state_machine.previous = state_machine.current;
     match target {
         None => {
             if pressed && !state_machine.current_btn_just_pressed {
                 match state_machine.current {
-                    GlobalPickingState::Pressed { .. } => (),
+                    GlobalPickingState::Pressed { .. } | GlobalPickingState::Dragging { .. } => (),
                     _ => state_machine.current = GlobalPickingState::None,
                 }
             } else {
@@ -437,20 +2083,394 @@ fn picking_state_machine_system(
         }
         Some(entity) if !pressed => state_machine.current = GlobalPickingState::Hover { entity },
         Some(entity) => {
-            let filter = if let Ok(filter) = filters.get(entity) {
-                filter.contains(state_machine.press.unwrap().button)
-            } else {
-                true
-            };
-            if filter {
+            let button = state_machine.press.unwrap().button;
+            // Extra buttons only press the entity that allows them.
+            if button_filters.press_allowed(entity, button) {
                 state_machine.current = GlobalPickingState::Pressed { entity }
-            } else {
+            } else if button_filters.hover_allowed(entity, button) {
                 state_machine.current = GlobalPickingState::Hover { entity }
+            } else {
+                state_machine.current = GlobalPickingState::None
+            }
+        }
+    }
+    // Presses that moved far enough become drags until released.
+    if let GlobalPickingState::Pressed { entity } = state_machine.current {
+        // A drag retained outside resumes on return.
+        let was_dragging = (state_machine.previous == GlobalPickingState::Dragging { entity }
+            || (state_machine.retained == Some(entity) && state_machine.retained_dragging))
+            && !state_machine.current_btn_just_pressed;
+        if was_dragging
+            || state_machine
+                .drag_offset()
+                .is_some_and(|x| x.length() > settings.drag_threshold)
+        {
+            state_machine.current = GlobalPickingState::Dragging { entity };
+        }
+    }
+    let mut retention = None;
+    let retained_dragging = state_machine.retained_dragging;
+    if settings.press_retention == PressRetention::ReleaseWhileOutside {
+        let pressed_entity = match state_machine.current {
+            GlobalPickingState::Pressed { entity } | GlobalPickingState::Dragging { entity } => {
+                Some(entity)
+            }
+            _ => None,
+        };
+        match (state_machine.current, state_machine.retained) {
+            (
+                GlobalPickingState::Pressed { entity } | GlobalPickingState::Dragging { entity },
+                _,
+            ) if pressed && !state_machine.is_post_cancellation_state && target != Some(entity) => {
+                state_machine.retained_dragging = state_machine.is_dragging();
+                state_machine.current = GlobalPickingState::None;
+                state_machine.retained = Some(entity);
+                retention = Some(PickingTransition::DragOutside { entity });
             }
+            (
+                GlobalPickingState::Pressed { entity } | GlobalPickingState::Dragging { entity },
+                Some(retained),
+            ) if entity == retained => {
+                state_machine.retained = None;
+                state_machine.retained_dragging = false;
+                retention = Some(PickingTransition::DragReturn { entity });
+            }
+            // The retained press also ends if another entity is pressed by `acquire_while_pressed`.
+            (_, Some(entity))
+                if !pressed
+                    || state_machine.is_post_cancellation_state
+                    || !entities.contains(entity)
+                    || pressed_entity.is_some_and(|x| x != entity) =>
+            {
+                state_machine.retained = None;
+                state_machine.retained_dragging = false;
+                let press = state_machine.press.unwrap_or(PressState {
+                    button: MouseButton::Left,
+                    position: Vec2::ZERO,
+                    time,
+                    source: state_machine.pointer_source.unwrap_or_default(),
+                    modifiers: Modifiers::NONE,
+                });
+                let (button, down, source) = (press.button, press.position, press.source);
+                let modifiers = press.modifiers;
+                let travel = state_machine.press_travel;
+                let time = time - press.time;
+                retention = if !entities.contains(entity) {
+                    None
+                } else if state_machine.is_post_cancellation_state {
+                    Some(PickingTransition::Cancelled {
+                        entity,
+                        button,
+                        down,
+                        time,
+                        chord: state_machine.chord.clone(),
+                        source,
+                        travel,
+                    })
+                } else {
+                    Some(PickingTransition::Released {
+                        entity,
+                        button,
+                        down,
+                        time,
+                        outside: target != Some(entity),
+                        source,
+                        travel,
+                        modifiers,
+                    })
+                };
+            }
+            _ => (),
+        }
+    }
+    if state_machine.suppressed {
+        state_machine.current = GlobalPickingState::None;
+    }
+    // Also computed when a button goes down during cancellation or another press.
+    #[cfg(feature = "debug")]
+    if state_machine.current_btn_just_pressed || state_machine.button_went_down {
+        state_machine.last_no_acquire_reason = match state_machine.current {
+            _ if state_machine.suppressed => Some(NoAcquireReason::Suppressed),
+            _ if state_machine.is_post_cancellation_state => {
+                Some(NoAcquireReason::PostCancellation)
+            }
+            GlobalPickingState::Pressed { .. } | GlobalPickingState::Dragging { .. } => None,
+            _ if !state_machine.current_btn_just_pressed && state_machine.press.is_some() => {
+                Some(NoAcquireReason::AcquireGated)
+            }
+            GlobalPickingState::Hover { .. } => Some(NoAcquireReason::Filtered),
+            GlobalPickingState::None => Some(NoAcquireReason::NoHit),
+        };
+    }
+    state_machine.queue_transitions(time, settings);
+    // Leaving or returning to a retained press is not a real release or press,
+    // and a retained drag continues until the press ends.
+    match retention {
+        Some(PickingTransition::DragOutside { entity }) => {
+            state_machine.transitions.retain(|x| {
+                !matches!(
+                    x,
+                    PickingTransition::Released { .. }
+                        | PickingTransition::HoverExit { .. }
+                        | PickingTransition::DragEnd { .. }
+                )
+            });
+            state_machine
+                .transitions
+                .push(PickingTransition::DragOutside { entity });
+        }
+        Some(PickingTransition::DragReturn { entity }) => {
+            state_machine.transitions.retain(|x| match x {
+                PickingTransition::Pressed { .. } => false,
+                PickingTransition::DragStart { .. } => !retained_dragging,
+                _ => true,
+            });
+            state_machine
+                .transitions
+                .insert(0, PickingTransition::DragReturn { entity });
+        }
+        Some(end) => {
+            let entity = end.entity();
+            state_machine.transitions.insert(0, end);
+            if retained_dragging {
+                state_machine
+                    .transitions
+                    .insert(0, PickingTransition::DragEnd { entity });
+            }
+        }
+        None => (),
+    }
+    if let Some((entity, pressed)) = lost {
+        state_machine.insert_lost(entity, pressed, time);
+    }
+    let pressed_entity = match state_machine.current {
+        GlobalPickingState::Pressed { entity } | GlobalPickingState::Dragging { entity } => {
+            Some(entity)
+        }
+        _ => state_machine.retained,
+    };
+    if let (Some(entity), Some((from, to))) = (pressed_entity, tracking_switched) {
+        state_machine
+            .transitions
+            .push(PickingTransition::TrackingSwitched { entity, from, to });
+    }
+    // Drop targets are whatever is on top, since the drag sticks to its entity.
+    let (dragged, drag_over) = match state_machine.current {
+        GlobalPickingState::Dragging { entity } => (Some(entity), state_machine.shadow_hover),
+        _ => (None, None),
+    };
+    if drag_over != state_machine.drag_over {
+        state_machine.drag_over = drag_over;
+        state_machine.drag_over_since = time;
+        state_machine.drop_armed = false;
+        if let Some(entity) = drag_over {
+            state_machine
+                .transitions
+                .push(PickingTransition::DragEnter {
+                    entity,
+                    is_source: dragged == Some(entity),
+                });
+        }
+    }
+    // Checked every frame since dwelling produces no input.
+    if let Some(entity) = drag_over.filter(|entity| dragged != Some(*entity)) {
+        if !state_machine.drop_armed && time - state_machine.drag_over_since >= settings.drop_dwell
+        {
+            state_machine.drop_armed = true;
+            state_machine
+                .transitions
+                .push(PickingTransition::DropTargetArmed { entity });
+        }
+    }
+    let toggled = state_machine.iter_transitions().find_map(|x| match x {
+        PickingTransition::Released {
+            entity,
+            outside: false,
+            ..
+        } if toggles.contains(entity) => Some(entity),
+        _ => None,
+    });
+    if let Some(entity) = toggled {
+        let on = !state_machine.toggled.remove(&entity);
+        if on {
+            state_machine.toggled.insert(entity);
+        }
+        state_machine
+            .transitions
+            .push(PickingTransition::Toggled { entity, on });
+    }
+    if let Some(selection) = selection {
+        let clicked = state_machine.iter_transitions().find_map(|x| match x {
+            PickingTransition::Released {
+                entity,
+                outside: false,
+                ..
+            } => Some(entity),
+            _ => None,
+        });
+        let previous = selection.selected;
+        match clicked {
+            Some(entity) if selectables.contains(entity) && previous != Some(entity) => {
+                selection.selected = Some(entity);
+                state_machine
+                    .transitions
+                    .push(PickingTransition::Selected { entity, previous });
+            }
+            None if selection.clear_on_empty_click
+                && state_machine.button_released
+                && state_machine.previous == GlobalPickingState::None =>
+            {
+                if let Some(entity) = selection.selected.take() {
+                    state_machine
+                        .transitions
+                        .push(PickingTransition::Deselected { entity });
+                }
+            }
+            _ => (),
+        }
+    }
+    // Per entity overrides of `multi_click_interval` and `multi_click_radius`.
+    let multi_click = |entity| match multi_click_configs.get(entity) {
+        Ok(config) => (config.interval, config.radius),
+        Err(_) => (settings.multi_click_interval, settings.multi_click_radius),
+    };
+    for transition in state_machine.collect_transitions() {
+        match transition {
+            PickingTransition::Pressed { entity, .. } => {
+                let (interval, _) = multi_click(entity);
+                state_machine.recent_press = state_machine
+                    .last_release
+                    .filter(|(e, t, ..)| *e == entity && time - t <= interval)
+                    .map(|(e, ..)| e);
+            }
+            // Releasing a drag is not a click.
+            PickingTransition::Released {
+                entity,
+                button,
+                outside: false,
+                ..
+            } if !matches!(state_machine.previous, GlobalPickingState::Dragging { .. }) => {
+                let pointer = state_machine.pointer;
+                let (interval, radius) = multi_click(entity);
+                let consecutive = state_machine.last_release.is_some_and(|(e, t, p, b)| {
+                    e == entity
+                        && b == button
+                        && time - t <= interval
+                        && pointer.distance(p) <= radius
+                });
+                state_machine.click_count = if consecutive {
+                    state_machine.click_count + 1
+                } else {
+                    1
+                };
+                state_machine.last_release = Some((entity, time, pointer, button));
+                let count = state_machine.click_count;
+                state_machine.transitions.push(PickingTransition::Clicked {
+                    entity,
+                    button,
+                    count,
+                });
+            }
+            #[cfg(feature = "debug")]
+            PickingTransition::HoverEnter { entity } => {
+                let (e, times) = state_machine
+                    .flicker
+                    .get_or_insert_with(|| (entity, VecDeque::new()));
+                if *e != entity {
+                    *e = entity;
+                    times.clear();
+                }
+                times.push_back(time);
+                while times.front().is_some_and(|t| time - t > 1.0) {
+                    times.pop_front();
+                }
+                if times.len() >= FLICKER_THRESHOLD {
+                    times.clear();
+                    state_machine.flicker_count += 1;
+                    bevy::log::warn_once!(
+                        "Entity {entity} is rapidly entering and exiting hover, \
+                        consider adding hysteresis to its hit area."
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+    if !state_machine.is_pressing() {
+        state_machine.recent_press = None;
+    }
+    if state_machine.current_btn_just_pressed {
+        state_machine.long_pressed = false;
+    }
+    // Checked every frame since holding still produces no input.
+    if let (Some(duration), GlobalPickingState::Pressed { entity }, Some(press)) = (
+        settings.long_press_duration,
+        state_machine.current,
+        state_machine.press,
+    ) {
+        if !state_machine.long_pressed
+            && !state_machine.is_post_cancellation_state
+            && time - press.time >= duration
+        {
+            state_machine.long_pressed = true;
+            state_machine
+                .transitions
+                .push(PickingTransition::LongPress {
+                    entity,
+                    button: press.button,
+                    position: press.position,
+                });
+        }
+    }
+    match state_machine.current {
+        GlobalPickingState::Pressed { entity } | GlobalPickingState::Dragging { entity }
+            if settings.emit_hit_updates =>
+        {
+            if let Some(hit) = target_hit.filter(|_| target == Some(entity)) {
+                if state_machine.last_hit.as_ref() != Some(hit) {
+                    state_machine
+                        .transitions
+                        .push(PickingTransition::HitUpdate {
+                            entity,
+                            hit: hit.clone(),
+                        });
+                    state_machine.last_hit = Some(hit.clone());
+                }
+            }
+        }
+        _ => state_machine.last_hit = None,
+    }
+    let active = state_machine.get_active_entity();
+    if let Some(hit) = target_hit.filter(|_| target.is_some() && target == active) {
+        state_machine.current_camera = Some(hit.camera);
+    } else if active.is_none() || active != state_machine.previous.current_entity() {
+        state_machine.current_camera = None;
+    }
+    if !state_machine.transitions.is_empty() {
+        state_machine.last_activity = time;
+    }
+    state_machine.resolved = state_machine.current;
+    if drag_end.is_some() {
+        state_machine.is_post_cancellation_state = true;
+    }
+    if state_machine.current_btn_just_pressed {
+        state_machine.drag_axis.clear();
+    }
+    // The first offset beyond any threshold is the first to set a new maximum beyond it.
+    if let Some(d) = state_machine.drag_offset().map(Vec2::abs) {
+        let max = d.max_element();
+        if state_machine.drag_axis.last().is_none_or(|(x, _)| max > *x) {
+            let axis = match d.x.total_cmp(&d.y) {
+                Ordering::Greater => Some(DragAxis::Horizontal),
+                Ordering::Less => Some(DragAxis::Vertical),
+                Ordering::Equal => None,
+            };
+            state_machine.drag_axis.push((max, axis));
         }
     }
-    state_machine.queue_transitions(time);
     if !pressed {
         state_machine.press = None;
+        state_machine.drag_axis.clear();
+        state_machine.long_pressed = false;
     }
 }
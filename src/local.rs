@@ -1,16 +1,134 @@
-use bevy::{ecs::component::Component, input::mouse::MouseButton};
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        resource::Resource,
+        system::{Query, Res, SystemParam},
+    },
+    input::mouse::MouseButton,
+};
+
+#[cfg(doc)]
+use crate::PickingStateMachine;
+use crate::PickingStateMachinePlugin;
 
 /// Filters which button can trigger an entity's `Pressed`.
 #[derive(Debug, Clone, Default, Component)]
-pub struct ButtonFilter(Vec<MouseButton>);
+pub struct ButtonFilter {
+    buttons: Vec<MouseButton>,
+    hover_buttons: Option<Vec<MouseButton>>,
+}
 
 impl ButtonFilter {
     pub fn new(iter: impl IntoIterator<Item = MouseButton>) -> Self {
-        ButtonFilter(iter.into_iter().collect())
+        ButtonFilter {
+            buttons: iter.into_iter().collect(),
+            hover_buttons: None,
+        }
+    }
+
+    /// Only hover the entity while no button is held or a held button is one of these.
+    ///
+    /// By default any held button that cannot press the entity still hovers it.
+    pub fn hover_only_with(mut self, iter: impl IntoIterator<Item = MouseButton>) -> Self {
+        self.hover_buttons = Some(iter.into_iter().collect());
+        self
+    }
+
+    pub fn contains(&self, btn: MouseButton) -> bool {
+        self.buttons.contains(&btn)
+    }
+
+    /// Returns true if the entity can be hovered while `btn` is held.
+    pub fn allows_hover(&self, btn: MouseButton) -> bool {
+        self.hover_buttons
+            .as_ref()
+            .is_none_or(|buttons| buttons.contains(&btn))
+    }
+}
+
+/// When an entity counts as activated by
+/// [`PropagatedPickingStateMachine::activated`](crate::PropagatedPickingStateMachine::activated).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Component)]
+pub enum ActivationMode {
+    /// Activate when pressed.
+    Press,
+    /// Activate when clicked, i.e. released inside without cancellation or dragging.
+    #[default]
+    Release,
+    /// Activate when the second click of a double click completes,
+    /// single clicks can still select the entity, see [`Selectable`].
+    DoubleClick,
+}
+
+/// Optional resource providing the [`ButtonFilter`] of entities without one.
+///
+/// If absent, entities without a [`ButtonFilter`] allow all buttons.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct DefaultButtonFilter(pub ButtonFilter);
+
+/// Buttons that are considered allowed while this entity is active,
+/// in addition to `PickingStateMachinePlugin::allowed_buttons`.
+///
+/// These buttons participate in press and cancellation logic only
+/// while the pointer is on this entity.
+#[derive(Debug, Clone, Default, Component)]
+pub struct ExtraButtons(Vec<MouseButton>);
+
+impl ExtraButtons {
+    pub fn new(iter: impl IntoIterator<Item = MouseButton>) -> Self {
+        ExtraButtons(iter.into_iter().collect())
     }
     pub fn contains(&self, btn: MouseButton) -> bool {
         self.0.contains(&btn)
     }
+    pub fn iter(&self) -> impl Iterator<Item = MouseButton> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// Marks an entity as a toggle, flipping a latched state on each click.
+///
+/// Use `PickingStateMachine::is_toggled` to read the state.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct ToggleButton;
+
+/// Marks an entity as selectable by [`SelectionState`].
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct Selectable;
+
+/// Optional resource tracking a single selected [`Selectable`] entity, updated on click.
+///
+/// Changes are emitted as `PickingTransition::Selected` and `PickingTransition::Deselected`.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct SelectionState {
+    pub(crate) selected: Option<Entity>,
+    /// If true, clicking outside of any entity clears the selection.
+    pub clear_on_empty_click: bool,
+}
+
+impl SelectionState {
+    pub fn new(clear_on_empty_click: bool) -> Self {
+        SelectionState {
+            selected: None,
+            clear_on_empty_click,
+        }
+    }
+
+    /// Returns the selected entity.
+    pub fn selected_entity(&self) -> Option<Entity> {
+        self.selected
+    }
+}
+
+/// Overrides `PickingStateMachinePlugin::multi_click_interval` and
+/// `PickingStateMachinePlugin::multi_click_radius` for clicks on this entity.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct MultiClickConfig {
+    /// Maximum seconds between the releases of consecutive clicks.
+    pub interval: f32,
+    /// Maximum distance in pixels between consecutive clicks.
+    pub radius: f32,
 }
 
 /// Optional component that increase or decrease the priority of the item.
@@ -21,3 +139,53 @@ pub struct PickPriority {
     /// Modifies (subtracts from) the distance from camera, bigger value gets prioritized.
     pub distance: f32,
 }
+
+/// Optional component that places the entity in a picking tier, 0 by default.
+///
+/// Only hits in the highest tier with any hit are considered,
+/// e.g. give HUD elements a higher tier to block the world behind them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Component)]
+pub struct PickTier(pub u8);
+
+/// [`SystemParam`] deciding whether a held button presses or only hovers an entity,
+/// as the state machine does.
+///
+/// Unlike [`PickingStateMachine::would_press`] this accounts for [`ExtraButtons`]
+/// and [`DefaultButtonFilter`], but not for cancellation.
+#[derive(SystemParam)]
+pub struct ButtonFilters<'w, 's> {
+    settings: Res<'w, PickingStateMachinePlugin>,
+    filters: Query<'w, 's, &'static ButtonFilter>,
+    extra_buttons: Query<'w, 's, &'static ExtraButtons>,
+    default_filter: Option<Res<'w, DefaultButtonFilter>>,
+}
+
+impl ButtonFilters<'_, '_> {
+    /// Returns the [`ButtonFilter`] of an entity, falling back to [`DefaultButtonFilter`].
+    fn filter(&self, entity: Entity) -> Option<&ButtonFilter> {
+        self.filters
+            .get(entity)
+            .ok()
+            .or(self.default_filter.as_deref().map(|x| &x.0))
+    }
+
+    /// Returns true if `button` presses `entity`, as opposed to only hovering it.
+    ///
+    /// The button must be allowed, either globally by `allowed_buttons` or by
+    /// the entity's [`ExtraButtons`], and then be permitted by the entity's [`ButtonFilter`],
+    /// falling back to [`DefaultButtonFilter`], falling back to allowing all buttons.
+    pub fn press_allowed(&self, entity: Entity, button: MouseButton) -> bool {
+        let allowed = self.settings.allowed_buttons.contains(&button)
+            || self
+                .extra_buttons
+                .get(entity)
+                .is_ok_and(|x| x.contains(button));
+        allowed && self.filter(entity).is_none_or(|x| x.contains(button))
+    }
+
+    /// Returns true if `entity` can still be hovered while `button` is held,
+    /// if `button` cannot press it, with the same fallback as [`ButtonFilters::press_allowed`].
+    pub fn hover_allowed(&self, entity: Entity, button: MouseButton) -> bool {
+        self.filter(entity).is_none_or(|x| x.allows_hover(button))
+    }
+}
@@ -1,11 +1,20 @@
-use bevy::ecs::{
-    component::Component,
-    entity::Entity,
-    hierarchy::ChildOf,
-    system::{Query, Res, SystemParam},
+use bevy::{
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::{Entity, EntityHashMap},
+        hierarchy::{ChildOf, Children},
+        lifecycle::RemovedComponents,
+        query::{Changed, Or, With},
+        resource::Resource,
+        system::{Query, Res, ResMut, SystemParam},
+    },
+    input::mouse::MouseButton,
 };
 
-use crate::{EntityPickingState, PickingStateMachine, PickingTransition};
+use crate::{
+    ActivationMode, ButtonFilter, EntityPickingState, PickingStateMachine, PickingTransition,
+};
 
 /// Determines what additional entities count as active by [`PropagatedPickingStateMachine`].
 ///
@@ -23,12 +32,139 @@ pub enum PickingPropagation {
     NoPropagation,
 }
 
+/// Marks an entity as a handler for [`PropagatedPickingStateMachine::routed_transitions`].
+#[derive(Debug, Clone, Copy, Component, Default)]
+pub struct PickHandler;
+
+/// Marks an entity as a propagation boundary for [`PropagatedPickingStateMachine`].
+///
+/// Propagation does not cross into or out of this entity's subtree,
+/// so sibling panels under a common parent stay independent.
+#[derive(Debug, Clone, Copy, Component, Default)]
+pub struct PickRoot;
+
+/// Optional resource caching the ancestors of entities up to a maximum depth,
+/// trading memory for faster [`PropagatedPickingStateMachine`] queries in large hierarchies.
+///
+/// Updated in [`PickingStateMachineSystems::Resolve`](crate::PickingStateMachineSystems::Resolve)
+/// for the subtrees where [`ChildOf`] or [`PickRoot`] changed.
+/// Hierarchy changes later in the frame are not seen until the next rebuild,
+/// entities missing from the cache fall back to walking the hierarchy.
+#[derive(Debug, Clone, Resource)]
+pub struct PropagationCache {
+    max_depth: usize,
+    ancestors: EntityHashMap<Vec<Entity>>,
+}
+
+impl PropagationCache {
+    /// Cache at most `max_depth` ancestors of each entity, deeper ancestors are walked.
+    pub fn new(max_depth: usize) -> Self {
+        PropagationCache {
+            max_depth,
+            ancestors: EntityHashMap::default(),
+        }
+    }
+}
+
+/// Hierarchy changes that invalidate [`PropagationCache`].
+type HierarchyChanged = Or<(Changed<ChildOf>, Changed<PickRoot>)>;
+
+pub(crate) fn propagation_cache_system(
+    mut cache: ResMut<PropagationCache>,
+    changed: Query<Entity, HierarchyChanged>,
+    mut removed_parents: RemovedComponents<ChildOf>,
+    mut removed_roots: RemovedComponents<PickRoot>,
+    parents: Query<(Entity, &ChildOf)>,
+    children: Query<&Children>,
+    roots: Query<(), With<PickRoot>>,
+) {
+    let added = cache.is_added();
+    let cache = &mut *cache;
+    let max_depth = cache.max_depth;
+    let chain = |entity| {
+        let mut chain = Vec::new();
+        let mut current = entity;
+        while chain.len() < max_depth && !roots.contains(current) {
+            let Ok((_, parent)) = parents.get(current) else {
+                break;
+            };
+            current = parent.parent();
+            chain.push(current);
+        }
+        chain
+    };
+    if added {
+        removed_parents.clear();
+        removed_roots.clear();
+        cache.ancestors = parents
+            .iter()
+            .map(|(entity, _)| (entity, chain(entity)))
+            .collect();
+        return;
+    }
+    // Only chains starting in the subtree of a changed entity, at most `max_depth` below it, contain it.
+    let mut stack: Vec<_> = changed
+        .iter()
+        .chain(removed_parents.read())
+        .chain(removed_roots.read())
+        .map(|entity| (entity, 0))
+        .collect();
+    let mut visited = EntityHashMap::<usize>::default();
+    while let Some((entity, depth)) = stack.pop() {
+        if visited.get(&entity).is_some_and(|x| *x <= depth) {
+            continue;
+        }
+        visited.insert(entity, depth);
+        if parents.contains(entity) {
+            cache.ancestors.insert(entity, chain(entity));
+        } else {
+            cache.ancestors.remove(&entity);
+        }
+        if depth < max_depth {
+            // Chains of `PickRoot` entities never leave them.
+            stack.extend(
+                children
+                    .get(entity)
+                    .into_iter()
+                    .flatten()
+                    .filter(|child| !roots.contains(**child))
+                    .map(|child| (*child, depth + 1)),
+            );
+        }
+    }
+}
+
 /// [`SystemParam`] that evaluates active entities through hierarchical propagation.
+///
+/// # Example
+///
+/// By default, a button is pressed while its child label is pressed.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_picking_state_machine::{EntityPickingState, PropagatedPickingStateMachine};
+/// #[derive(Component)]
+/// struct MyButton;
+///
+/// fn button_system(
+///     state_machine: PropagatedPickingStateMachine,
+///     buttons: Query<Entity, With<MyButton>>,
+/// ) {
+///     for button in &buttons {
+///         if let EntityPickingState::Pressed = state_machine.get_state(button) {
+///             println!("{button} is pressed");
+///         }
+///     }
+/// }
+/// # bevy::ecs::system::assert_is_system(button_system);
+/// ```
 #[derive(Debug, SystemParam)]
 pub struct PropagatedPickingStateMachine<'w, 's> {
     pub state_machine: Res<'w, PickingStateMachine>,
     pub parents: Query<'w, 's, &'static ChildOf>,
     pub propagation: Query<'w, 's, &'static PickingPropagation>,
+    pub roots: Query<'w, 's, (), With<PickRoot>>,
+    pub cache: Option<Res<'w, PropagationCache>>,
 }
 
 impl PropagatedPickingStateMachine<'_, '_> {
@@ -40,57 +176,110 @@ impl PropagatedPickingStateMachine<'_, '_> {
         match self.propagation.get(active) {
             Ok(PickingPropagation::NoPropagation) => active == to,
             Ok(PickingPropagation::PropagateDown) | Err(_) => {
-                let mut current = to;
-                while let Ok(parent) = self.parents.get(current) {
-                    if parent.parent() == active {
-                        return true;
-                    }
-                    current = parent.parent();
-                }
-                false
+                self.ancestors(to).any(|parent| parent == active)
             }
             Ok(PickingPropagation::PropagateUp(count)) => {
-                let mut root = active;
-                for _ in 0..*count {
-                    if let Ok(parent) = self.parents.get(root) {
-                        root = parent.parent();
-                    } else {
-                        break;
-                    }
-                }
-                let mut current = to;
-                while let Ok(parent) = self.parents.get(current) {
-                    if parent.parent() == active || parent.parent() == root {
-                        return true;
-                    }
-                    current = parent.parent();
-                }
-                false
+                let root = self.ancestors(active).take(*count).last().unwrap_or(active);
+                self.ancestors(to)
+                    .any(|parent| parent == active || parent == root)
             }
             Ok(PickingPropagation::AndPropagateUp(count)) => {
-                let mut current = to;
-                while let Ok(parent) = self.parents.get(current) {
-                    if parent.parent() == active {
-                        return true;
-                    }
-                    current = parent.parent();
-                }
-                let mut current = active;
-                for _ in 0..*count {
-                    if let Ok(parent) = self.parents.get(current) {
-                        current = parent.parent();
-                        if current == to {
-                            return true;
-                        }
-                    } else {
-                        return false;
-                    }
-                }
-                false
+                self.ancestors(to).any(|parent| parent == active)
+                    || self
+                        .ancestors(active)
+                        .take(*count)
+                        .any(|parent| parent == to)
             }
         }
     }
 
+    /// Ancestors of an entity for propagation, nearest first,
+    /// read from [`PropagationCache`] if present.
+    fn ancestors(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+        let cached = self
+            .cache
+            .as_deref()
+            .and_then(|cache| cache.ancestors.get(&entity))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        // Chains are truncated at `max_depth`, walk the rest.
+        let walk_from = cached.last().copied().unwrap_or(entity);
+        cached
+            .iter()
+            .copied()
+            .chain(core::iter::successors(self.parent(walk_from), |current| {
+                self.parent(*current)
+            }))
+    }
+
+    /// Parent of an entity for propagation, [`PickRoot`] entities have none.
+    fn parent(&self, entity: Entity) -> Option<Entity> {
+        if self.roots.contains(entity) {
+            return None;
+        }
+        self.parents.get(entity).ok().map(ChildOf::parent)
+    }
+
+    /// Entities equivalent to `active`, found by walking its ancestors and their descendants
+    /// instead of testing every entity.
+    pub(crate) fn equivalent_entities(
+        &self,
+        active: Entity,
+        children: &Query<&Children>,
+    ) -> Vec<Entity> {
+        let up = match self.propagation.get(active) {
+            Ok(PickingPropagation::NoPropagation) => return vec![active],
+            Ok(PickingPropagation::PropagateDown) | Err(_) => 0,
+            Ok(PickingPropagation::PropagateUp(count))
+            | Ok(PickingPropagation::AndPropagateUp(count)) => *count,
+        };
+        let mut candidates: Vec<Entity> = self.ancestors(active).take(up).collect();
+        let mut stack = vec![candidates.last().copied().unwrap_or(active)];
+        // Propagation never crosses into the subtree of a `PickRoot`.
+        while let Some(entity) = stack.pop() {
+            candidates.push(entity);
+            stack.extend(
+                children
+                    .get(entity)
+                    .into_iter()
+                    .flatten()
+                    .filter(|child| !self.roots.contains(**child)),
+            );
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates.retain(|entity| self.entity_equivalent(active, *entity));
+        candidates
+    }
+
+    /// Number of ancestors of an entity for propagation.
+    fn depth(&self, entity: Entity) -> usize {
+        self.ancestors(entity).count()
+    }
+
+    /// Among `candidates`, find the deepest entity in the hierarchy
+    /// that is equivalent to the active entity.
+    pub fn deepest_active(&self, candidates: impl IntoIterator<Item = Entity>) -> Option<Entity> {
+        let active = self.state_machine.get_active_entity()?;
+        candidates
+            .into_iter()
+            .filter(|x| self.entity_equivalent(active, *x))
+            .max_by_key(|x| self.depth(*x))
+    }
+
+    /// Among `candidates`, find the shallowest entity in the hierarchy
+    /// that is equivalent to the active entity.
+    pub fn shallowest_active(
+        &self,
+        candidates: impl IntoIterator<Item = Entity>,
+    ) -> Option<Entity> {
+        let active = self.state_machine.get_active_entity()?;
+        candidates
+            .into_iter()
+            .filter(|x| self.entity_equivalent(active, *x))
+            .min_by_key(|x| self.depth(*x))
+    }
+
     /// Get the state of an entity, accounting for event propagations.
     pub fn get_state(&self, entity: Entity) -> EntityPickingState {
         let Some(active_entity) = self.state_machine.get_active_entity() else {
@@ -103,12 +292,58 @@ impl PropagatedPickingStateMachine<'_, '_> {
         }
     }
 
+    /// Pair each transition with the nearest [`PickHandler`] among
+    /// the transition's entity and its ancestors, up to a [`PickRoot`].
+    ///
+    /// Transitions without a handler are skipped.
+    pub fn routed_transitions(
+        &self,
+        handlers: &Query<Entity, With<PickHandler>>,
+    ) -> impl Iterator<Item = (Entity, PickingTransition)> {
+        self.state_machine.iter_transitions().filter_map(move |x| {
+            let entity = x.entity();
+            core::iter::once(entity)
+                .chain(self.ancestors(entity))
+                .find(|current| handlers.contains(*current))
+                .map(|handler| (handler, x))
+        })
+    }
+
+    /// Returns the button that activated an entity this frame, accounting for event propagations.
+    ///
+    /// The entity's [`ActivationMode`] decides between activating on press, click or double click,
+    /// entities without one activate on click, so drags and cancellations never activate.
+    /// Buttons denied by the entity's [`ButtonFilter`] are ignored.
+    pub fn activated(
+        &self,
+        entity: Entity,
+        filters: &Query<&ButtonFilter>,
+        modes: &Query<&ActivationMode>,
+    ) -> Option<MouseButton> {
+        let mode = modes.get(entity).copied().unwrap_or_default();
+        let filter = filters.get(entity).ok();
+        self.get_transitions(entity).find_map(|x| {
+            let button = match (mode, x) {
+                (ActivationMode::Press, PickingTransition::Pressed { button, .. }) => button,
+                (ActivationMode::Release, PickingTransition::Clicked { button, .. }) => button,
+                (
+                    ActivationMode::DoubleClick,
+                    PickingTransition::Clicked {
+                        button, count: 2, ..
+                    },
+                ) => button,
+                _ => return None,
+            };
+            filter.is_none_or(|f| f.contains(button)).then_some(button)
+        })
+    }
+
     /// Get the state transition event of an entity, accounting for event propagations.
     pub fn get_transitions(&self, entity: Entity) -> impl Iterator<Item = PickingTransition> {
         self.state_machine
             .transitions
             .iter()
-            .copied()
             .filter(move |x| self.entity_equivalent(x.entity(), entity))
+            .cloned()
     }
 }
@@ -0,0 +1,128 @@
+use core::marker::PhantomData;
+
+use bevy::{
+    app::{Plugin, PreUpdate},
+    ecs::{
+        component::{Component, Mutable},
+        entity::EntityHashSet,
+        hierarchy::Children,
+        schedule::IntoScheduleConfigs,
+        system::Query,
+    },
+    input::mouse::MouseButton,
+};
+
+use crate::{
+    AllPickingStateMachines, PickingStateMachineSystems, PickingTransition,
+    PropagatedPickingStateMachine,
+};
+
+/// A component reacting to the transitions of its entity,
+/// accounting for event propagations.
+///
+/// Register with [`PickReactivePlugin`] or add [`apply_pick_reactions`] manually.
+/// All methods do nothing by default.
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_picking_state_machine::{PickReactive, PickReactivePlugin};
+/// #[derive(Component)]
+/// struct PressCounter(usize);
+///
+/// impl PickReactive for PressCounter {
+///     fn on_press(&mut self, _: MouseButton) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// # let mut app = App::new();
+/// app.add_plugins(PickReactivePlugin::<PressCounter>::default());
+/// ```
+pub trait PickReactive {
+    /// Called on [`PickingTransition::HoverEnter`].
+    fn on_hover_enter(&mut self) {}
+    /// Called on [`PickingTransition::HoverExit`].
+    fn on_hover_exit(&mut self) {}
+    /// Called on [`PickingTransition::Pressed`].
+    fn on_press(&mut self, button: MouseButton) {
+        let _ = button;
+    }
+    /// Called on [`PickingTransition::Released`], inside or outside.
+    fn on_release(&mut self, button: MouseButton, outside: bool) {
+        let _ = (button, outside);
+    }
+    /// Called on [`PickingTransition::Cancelled`].
+    fn on_cancel(&mut self, button: MouseButton) {
+        let _ = button;
+    }
+    /// Called on [`PickingTransition::Clicked`].
+    fn on_click(&mut self, button: MouseButton, count: u32) {
+        let _ = (button, count);
+    }
+}
+
+/// Adds [`apply_pick_reactions`] for `C` in [`PickingStateMachineSystems::Dispatch`].
+pub struct PickReactivePlugin<C>(PhantomData<fn() -> C>);
+
+impl<C> Default for PickReactivePlugin<C> {
+    fn default() -> Self {
+        PickReactivePlugin(PhantomData)
+    }
+}
+
+impl<C: PickReactive + Component<Mutability = Mutable>> Plugin for PickReactivePlugin<C> {
+    fn build(&self, app: &mut bevy::app::App) {
+        app.add_systems(
+            PreUpdate,
+            apply_pick_reactions::<C>.in_set(PickingStateMachineSystems::Dispatch),
+        );
+    }
+}
+
+/// Calls the [`PickReactive`] methods of `C` for each transition of the current frame
+/// in any window, on the transition's entity and entities equivalent to it.
+///
+/// Only the components of these entities are visited, found by walking the hierarchy
+/// around each transition's entity.
+pub fn apply_pick_reactions<C: PickReactive + Component<Mutability = Mutable>>(
+    propagated: PropagatedPickingStateMachine,
+    state_machines: AllPickingStateMachines,
+    children: Query<&Children>,
+    mut components: Query<&mut C>,
+) {
+    // Entities equivalent to any transition's entity, in order of their first transition.
+    let mut seen = EntityHashSet::default();
+    let mut affected = Vec::new();
+    for transition in state_machines.iter_transitions() {
+        for entity in propagated.equivalent_entities(transition.entity(), &children) {
+            if seen.insert(entity) {
+                affected.push(entity);
+            }
+        }
+    }
+    for entity in affected {
+        let Ok(mut component) = components.get_mut(entity) else {
+            continue;
+        };
+        let transitions = state_machines
+            .iter_transitions()
+            .filter(|x| propagated.entity_equivalent(x.entity(), entity));
+        for transition in transitions {
+            match transition {
+                PickingTransition::HoverEnter { .. } => component.on_hover_enter(),
+                PickingTransition::HoverExit { .. } => component.on_hover_exit(),
+                PickingTransition::Pressed { button, .. } => component.on_press(button),
+                PickingTransition::Released {
+                    button, outside, ..
+                } => component.on_release(button, outside),
+                PickingTransition::Cancelled { button, .. } => component.on_cancel(button),
+                PickingTransition::Clicked { button, count, .. } => {
+                    component.on_click(button, count)
+                }
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,2706 @@
+use core::time::Duration;
+
+use bevy::{
+    MinimalPlugins,
+    app::{App, PreUpdate},
+    camera::RenderTarget,
+    ecs::{
+        change_detection::Mut,
+        entity::Entity,
+        hierarchy::ChildOf,
+        query::With,
+        schedule::IntoScheduleConfigs,
+        system::{Query, RunSystemOnce},
+    },
+    input::{
+        InputPlugin, InputSystems,
+        gamepad::GamepadButton,
+        mouse::MouseButton,
+        touch::{TouchInput, TouchPhase},
+    },
+    math::Vec2,
+    picking::{
+        PickingSystems,
+        backend::{HitData, PointerHits},
+        pointer::PointerId,
+    },
+    time::TimeUpdateStrategy,
+    window::{PrimaryWindow, Window, WindowRef},
+};
+
+use crate::{
+    EntityPickingState, GlobalPickingState, HitTiebreak, MultiButtonPolicy, PickHandler, PickRoot,
+    PickingInputOverride, PickingPropagation, PickingStateMachine, PickingStateMachinePlugin,
+    PickingStateMachines, PickingTransition, PointerOrigin, PointerSource, PressRetention,
+    PropagatedPickingStateMachine, PropagationCache,
+};
+
+/// Drives the plugin with [`PickingInputOverride`] and injected hits, one frame at a time.
+struct Harness {
+    app: App,
+    camera: Entity,
+    pointer: Vec2,
+}
+
+impl Harness {
+    fn new(plugin: PickingStateMachinePlugin) -> Self {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, InputPlugin, plugin));
+        // Like `PickingPlugin`, which is not added.
+        app.configure_sets(PreUpdate, PickingSystems::Hover.after(InputSystems));
+        app.add_message::<PointerHits>();
+        app.init_resource::<PickingInputOverride>();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            16,
+        )));
+        let camera = app.world_mut().spawn_empty().id();
+        Harness {
+            app,
+            camera,
+            pointer: Vec2::ZERO,
+        }
+    }
+
+    fn spawn(&mut self) -> Entity {
+        self.app.world_mut().spawn_empty().id()
+    }
+
+    fn write_hits(&mut self, hits: &[Entity]) {
+        let picks = hits
+            .iter()
+            .enumerate()
+            .map(|(i, entity)| (*entity, HitData::new(self.camera, i as f32, None, None)))
+            .collect();
+        let hits = PointerHits::new(PointerId::Mouse, picks, 0.0);
+        self.app.world_mut().write_message(hits);
+    }
+
+    /// Run a frame with `hits` under the pointer, topmost first, while holding `buttons`.
+    fn frame(&mut self, hits: &[Entity], buttons: &[MouseButton]) -> Vec<PickingTransition> {
+        self.write_hits(hits);
+        let mut input = self.app.world_mut().resource_mut::<PickingInputOverride>();
+        input.pointer = Some(self.pointer);
+        input.buttons = Some(buttons.to_vec());
+        self.app.update();
+        self.machine().iter_transitions().collect()
+    }
+
+    /// Run a frame like [`Harness::frame`] without overriding the pointer.
+    fn frame_touch(&mut self, hits: &[Entity], buttons: &[MouseButton]) -> Vec<PickingTransition> {
+        self.write_hits(hits);
+        let mut input = self.app.world_mut().resource_mut::<PickingInputOverride>();
+        input.pointer = None;
+        input.buttons = Some(buttons.to_vec());
+        self.app.update();
+        self.machine().iter_transitions().collect()
+    }
+
+    /// Move the pointer, then run a frame like [`Harness::frame`].
+    fn frame_at(
+        &mut self,
+        pointer: Vec2,
+        hits: &[Entity],
+        buttons: &[MouseButton],
+    ) -> Vec<PickingTransition> {
+        self.pointer = pointer;
+        self.frame(hits, buttons)
+    }
+
+    fn machine(&self) -> &PickingStateMachine {
+        self.app.world().resource::<PickingStateMachine>()
+    }
+
+    fn machine_mut(&mut self) -> Mut<'_, PickingStateMachine> {
+        self.app.world_mut().resource_mut::<PickingStateMachine>()
+    }
+}
+
+fn kinds(transitions: &[PickingTransition]) -> Vec<&'static str> {
+    transitions
+        .iter()
+        .map(|x| match x {
+            PickingTransition::Pressed { .. } => "Pressed",
+            PickingTransition::Released { outside: false, .. } => "Released",
+            PickingTransition::Released { outside: true, .. } => "ReleasedOutside",
+            PickingTransition::HoverEnter { .. } => "HoverEnter",
+            PickingTransition::HoverExit { .. } => "HoverExit",
+            PickingTransition::Cancelled { .. } => "Cancelled",
+            PickingTransition::Toggled { .. } => "Toggled",
+            PickingTransition::Clicked { .. } => "Clicked",
+            PickingTransition::LongPress { .. } => "LongPress",
+            PickingTransition::DragStart { .. } => "DragStart",
+            PickingTransition::DragMove { .. } => "DragMove",
+            PickingTransition::DragEnd { .. } => "DragEnd",
+            PickingTransition::DragOutside { .. } => "DragOutside",
+            PickingTransition::DragReturn { .. } => "DragReturn",
+            PickingTransition::DragEnter { .. } => "DragEnter",
+            PickingTransition::TrackingSwitched { .. } => "TrackingSwitched",
+            PickingTransition::DropTargetArmed { .. } => "DropTargetArmed",
+            PickingTransition::Selected { .. } => "Selected",
+            PickingTransition::Deselected { .. } => "Deselected",
+            PickingTransition::HitUpdate { .. } => "HitUpdate",
+            PickingTransition::Scrolled { .. } => "Scrolled",
+            PickingTransition::Lost { .. } => "Lost",
+        })
+        .collect()
+}
+
+const LEFT: &[MouseButton] = &[MouseButton::Left];
+const NONE: &[MouseButton] = &[];
+
+#[test]
+fn disallowed_button_keeps_hover() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["HoverEnter"]);
+    // A pen barrel button reported as a disallowed button.
+    let barrel = &[MouseButton::Other(3)];
+    assert!(harness.frame(&[a], barrel).is_empty());
+    assert_eq!(
+        harness.machine().current,
+        GlobalPickingState::Hover { entity: a }
+    );
+    assert!(!harness.machine().is_cancelled());
+    // Pressing an allowed button while holding it is a normal press.
+    let chord = &[MouseButton::Left, MouseButton::Other(3)];
+    assert_eq!(kinds(&harness.frame(&[a], chord)), ["Pressed"]);
+    assert_eq!(kinds(&harness.frame(&[a], barrel)), ["Released", "Clicked"]);
+    assert!(!harness.machine().is_cancelled());
+}
+
+#[test]
+fn press_and_release() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    assert_eq!(kinds(&harness.frame(&[a], LEFT)), ["Pressed"]);
+    assert!(harness.frame_at(Vec2::new(1.0, 1.0), &[a], LEFT).is_empty());
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["Released", "Clicked"]);
+    assert_eq!(kinds(&harness.frame(&[], NONE)), ["HoverExit"]);
+}
+
+/// Checks the per frame contract: at most one transition of each kind,
+/// and every press ends exactly once before the next one starts.
+#[derive(Default)]
+struct PressTracker {
+    pressed: Option<Entity>,
+}
+
+impl PressTracker {
+    fn check(&mut self, machine: &PickingStateMachine, transitions: &[PickingTransition]) {
+        for (i, a) in transitions.iter().enumerate() {
+            assert!(
+                transitions[..i].iter().all(|b| !a.same_kind(b)),
+                "duplicate kind in {transitions:?}"
+            );
+        }
+        for transition in transitions {
+            match *transition {
+                PickingTransition::Pressed { entity, .. } => {
+                    assert_eq!(self.pressed, None, "pressed twice in {transitions:?}");
+                    self.pressed = Some(entity);
+                }
+                PickingTransition::Released { entity, .. }
+                | PickingTransition::Cancelled { entity, .. } => {
+                    assert_eq!(
+                        self.pressed,
+                        Some(entity),
+                        "unmatched end in {transitions:?}"
+                    );
+                    self.pressed = None;
+                }
+                _ => (),
+            }
+        }
+        let pressing = machine.is_pressing().then(|| machine.get_active_entity());
+        assert_eq!(
+            pressing.flatten(),
+            self.pressed,
+            "state {:?}",
+            machine.current
+        );
+    }
+}
+
+/// Every combination of hits and held buttons used by the permutation suites.
+fn inputs(a: Entity, b: Entity) -> Vec<(Vec<Entity>, Vec<MouseButton>)> {
+    let hits = [vec![], vec![a], vec![b], vec![a, b], vec![b, a]];
+    let buttons = [
+        vec![],
+        vec![MouseButton::Left],
+        vec![MouseButton::Right],
+        vec![MouseButton::Left, MouseButton::Right],
+    ];
+    hits.iter()
+        .flat_map(|h| buttons.iter().map(move |b| (h.clone(), b.clone())))
+        .collect()
+}
+
+fn permutation_plugins() -> Vec<PickingStateMachinePlugin> {
+    let builder = || {
+        PickingStateMachinePlugin::builder()
+            .allowed_buttons([MouseButton::Left, MouseButton::Right])
+    };
+    vec![
+        builder().build(),
+        builder().acquire_while_pressed(true).build(),
+        builder().resume_after_chord(true).build(),
+        builder().cancel_hover(true).build(),
+        builder()
+            .drag_threshold(4.0)
+            .acquire_while_pressed(true)
+            .build(),
+        builder()
+            .multi_button_policy(MultiButtonPolicy::Priority)
+            .build(),
+        builder()
+            .multi_button_policy(MultiButtonPolicy::FirstWins)
+            .build(),
+    ]
+}
+
+#[test]
+fn one_transition_of_each_kind_for_all_pairs() {
+    for plugin in permutation_plugins() {
+        let mut harness = Harness::new(plugin);
+        let (a, b) = (harness.spawn(), harness.spawn());
+        let inputs = inputs(a, b);
+        let mut tracker = PressTracker::default();
+        let idle = (vec![], vec![]);
+        for first in &inputs {
+            for second in &inputs {
+                // Idle frames in between return to `None` and end any cancellation.
+                for (hits, buttons) in [first, second, first, &idle, &idle] {
+                    let transitions = harness.frame(hits, buttons);
+                    tracker.check(harness.machine(), &transitions);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn one_transition_of_each_kind_for_random_sequences() {
+    for plugin in permutation_plugins() {
+        let mut harness = Harness::new(plugin);
+        let (a, b) = (harness.spawn(), harness.spawn());
+        let inputs = inputs(a, b);
+        let mut tracker = PressTracker::default();
+        // A fixed linear congruential generator keeps failures reproducible.
+        let mut seed = 0x2545_f491_u32;
+        for frame in 0..1000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let (hits, buttons) = &inputs[(seed >> 16) as usize % inputs.len()];
+            let pointer = Vec2::new((frame % 7) as f32 * 3.0, 0.0);
+            let transitions = harness.frame_at(pointer, hits, buttons);
+            tracker.check(harness.machine(), &transitions);
+        }
+    }
+}
+
+#[test]
+fn drag_enter_marks_the_source() {
+    let mut harness = Harness::new(
+        PickingStateMachinePlugin::builder()
+            .drag_threshold(4.0)
+            .build(),
+    );
+    let (a, b) = (harness.spawn(), harness.spawn());
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    let transitions = harness.frame_at(Vec2::new(10.0, 0.0), &[a], LEFT);
+    assert!(transitions.contains(&PickingTransition::DragEnter {
+        entity: a,
+        is_source: true
+    }));
+    let transitions = harness.frame_at(Vec2::new(20.0, 0.0), &[b, a], LEFT);
+    assert!(transitions.contains(&PickingTransition::DragEnter {
+        entity: b,
+        is_source: false
+    }));
+    assert_eq!(harness.machine().drag_over(), Some(b));
+    // Staying over the same entity enters nothing.
+    let transitions = harness.frame_at(Vec2::new(30.0, 0.0), &[b, a], LEFT);
+    assert_eq!(kinds(&transitions), ["DragMove"]);
+    harness.frame(&[b, a], NONE);
+    assert_eq!(harness.machine().drag_over(), None);
+}
+
+#[test]
+fn drop_target_arms_once_after_dwell() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .drag_threshold(4.0)
+        .drop_dwell(0.1);
+    let mut harness = Harness::new(plugin.build());
+    let (a, b) = (harness.spawn(), harness.spawn());
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.frame_at(Vec2::new(10.0, 0.0), &[b, a], LEFT);
+    let mut armed = Vec::new();
+    for _ in 0..20 {
+        armed.extend(
+            harness
+                .frame(&[b, a], LEFT)
+                .into_iter()
+                .filter(|x| matches!(x, PickingTransition::DropTargetArmed { .. })),
+        );
+    }
+    assert_eq!(armed, [PickingTransition::DropTargetArmed { entity: b }]);
+    // Moving away resets the dwell, the dragged entity itself is never armed.
+    for _ in 0..20 {
+        let transitions = harness.frame(&[a], LEFT);
+        assert!(!kinds(&transitions).contains(&"DropTargetArmed"));
+    }
+    harness.frame(&[b, a], LEFT);
+    let transitions = harness.frame(&[b, a], LEFT);
+    assert!(!kinds(&transitions).contains(&"DropTargetArmed"));
+}
+
+#[test]
+fn per_frame_flags_reset_while_keyboard_owned() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    assert!(harness.machine().pointer_over_interactive());
+    assert_eq!(harness.machine().shadow_hover(), Some(a));
+    harness.machine_mut().keyboard_hover(a);
+    harness.frame(&[], NONE);
+    assert!(!harness.machine().pointer_over_interactive());
+    assert_eq!(harness.machine().shadow_hover(), None);
+    // Releases are per frame as well.
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.frame(&[a], NONE);
+    assert!(harness.machine().button_released);
+    harness.machine_mut().keyboard_hover(a);
+    harness.frame(&[a], NONE);
+    assert!(!harness.machine().button_released);
+}
+
+fn cancelled_chord(transitions: &[PickingTransition]) -> Option<Vec<MouseButton>> {
+    transitions.iter().find_map(|x| match x {
+        PickingTransition::Cancelled { chord, .. } => Some(chord.to_vec()),
+        _ => None,
+    })
+}
+
+const LEFT_RIGHT: &[MouseButton] = &[MouseButton::Left, MouseButton::Right];
+
+#[test]
+fn chord_reports_the_tracked_button_first() {
+    let plugin = PickingStateMachinePlugin::builder().allowed_button(MouseButton::Right);
+    for (tracked, other) in [
+        (MouseButton::Left, MouseButton::Right),
+        (MouseButton::Right, MouseButton::Left),
+    ] {
+        let mut harness = Harness::new(plugin.clone().build());
+        let a = harness.spawn();
+        harness.frame(&[a], NONE);
+        assert_eq!(kinds(&harness.frame(&[a], &[tracked])), ["Pressed"]);
+        let transitions = harness.frame(&[a], LEFT_RIGHT);
+        assert_eq!(cancelled_chord(&transitions), Some(vec![tracked, other]));
+        assert_eq!(harness.machine().chord.as_slice(), [tracked, other]);
+        harness.frame(&[a], NONE);
+        assert!(harness.machine().chord.is_empty());
+    }
+}
+
+#[test]
+fn request_cancel_has_no_chord() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.machine_mut().request_cancel();
+    let transitions = harness.frame(&[a], LEFT);
+    assert_eq!(cancelled_chord(&transitions), Some(vec![]));
+}
+
+#[cfg(feature = "debug")]
+#[test]
+fn presses_during_cancellation_report_post_cancellation() {
+    use crate::NoAcquireReason;
+
+    let reason = |harness: &Harness| harness.machine().last_no_acquire_reason;
+    let plugin = PickingStateMachinePlugin::builder().allowed_button(MouseButton::Right);
+    let mut harness = Harness::new(plugin.clone().build());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(reason(&harness), None);
+    // Left pressed again while right is held after a chord.
+    harness.frame(&[a], LEFT_RIGHT);
+    harness.frame(&[a], &[MouseButton::Right]);
+    harness.frame(&[a], LEFT_RIGHT);
+    assert_eq!(reason(&harness), Some(NoAcquireReason::PostCancellation));
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(reason(&harness), None);
+    // Both pressed at once.
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT_RIGHT);
+    assert_eq!(reason(&harness), Some(NoAcquireReason::PostCancellation));
+    // Pressed after `request_cancel`.
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.machine_mut().request_cancel();
+    harness.frame(&[a], LEFT);
+    harness.frame(&[a], LEFT_RIGHT);
+    assert_eq!(reason(&harness), Some(NoAcquireReason::PostCancellation));
+    // Held on nothing, then pressed over an entity.
+    let plugin = plugin.multi_button_policy(MultiButtonPolicy::FirstWins);
+    let mut harness = Harness::new(plugin.build());
+    let a = harness.spawn();
+    harness.frame(&[], LEFT);
+    harness.frame(&[a], LEFT_RIGHT);
+    assert_eq!(reason(&harness), Some(NoAcquireReason::AcquireGated));
+    harness.frame(&[], NONE);
+    harness.frame(&[], LEFT);
+    assert_eq!(reason(&harness), Some(NoAcquireReason::NoHit));
+}
+
+#[test]
+fn disallowing_buttons_keeps_hover() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness
+        .app
+        .world_mut()
+        .resource_mut::<PickingStateMachinePlugin>()
+        .allowed_buttons = vec![MouseButton::Right];
+    assert!(harness.frame(&[a], NONE).is_empty());
+    assert_eq!(
+        harness.machine().current,
+        GlobalPickingState::Hover { entity: a }
+    );
+    // The removed button no longer presses.
+    assert!(harness.frame(&[a], LEFT).is_empty());
+}
+
+#[test]
+fn disallowing_the_tracked_button_cancels_without_chord() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness
+        .app
+        .world_mut()
+        .resource_mut::<PickingStateMachinePlugin>()
+        .allowed_buttons = vec![MouseButton::Right];
+    let transitions = harness.frame(&[a], LEFT);
+    assert_eq!(kinds(&transitions), ["Cancelled"]);
+    assert_eq!(cancelled_chord(&transitions), Some(vec![]));
+    assert!(harness.machine().is_cancelled());
+    harness.frame(&[a], NONE);
+    assert!(!harness.machine().is_cancelled());
+}
+
+#[test]
+fn first_wins_switches_tracking_to_the_held_button() {
+    let mut harness = Harness::new(
+        PickingStateMachinePlugin::builder()
+            .allowed_button(MouseButton::Right)
+            .multi_button_policy(MultiButtonPolicy::FirstWins)
+            .build(),
+    );
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    assert_eq!(kinds(&harness.frame(&[a], LEFT)), ["Pressed"]);
+    assert!(harness.frame(&[a], LEFT_RIGHT).is_empty());
+    let transitions = harness.frame(&[a], &[MouseButton::Right]);
+    assert_eq!(
+        transitions,
+        [PickingTransition::TrackingSwitched {
+            entity: a,
+            from: MouseButton::Left,
+            to: MouseButton::Right,
+        }]
+    );
+    assert_eq!(harness.machine().tracked_button(), Some(MouseButton::Right));
+    assert!(harness.machine().is_pressing());
+    let transitions = harness.frame(&[a], NONE);
+    assert_eq!(kinds(&transitions), ["Released", "Clicked"]);
+    assert_eq!(transitions[0].button(), Some(MouseButton::Right));
+}
+
+#[test]
+fn press_source_follows_the_pointer() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    // Overridden pointers are programmatic.
+    let source = harness.machine().press.map(|x| x.source);
+    assert_eq!(source, Some(PointerSource::Programmatic));
+    harness.frame(&[a], NONE);
+
+    let window = harness
+        .app
+        .world_mut()
+        .spawn((Window::default(), PrimaryWindow))
+        .id();
+    harness.app.world_mut().write_message(TouchInput {
+        phase: TouchPhase::Started,
+        position: Vec2::new(10.0, 10.0),
+        window,
+        force: None,
+        id: 0,
+    });
+    let transitions = harness.frame_touch(&[a], LEFT);
+    assert!(transitions.iter().any(|x| matches!(
+        x,
+        PickingTransition::Pressed {
+            source: PointerSource::Touch,
+            ..
+        }
+    )));
+}
+
+#[test]
+fn resume_after_chord_resumes_the_cancelled_press() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .allowed_button(MouseButton::Right)
+        .resume_after_chord(true)
+        .build();
+    for (tracked, other) in [
+        (MouseButton::Left, MouseButton::Right),
+        (MouseButton::Right, MouseButton::Left),
+    ] {
+        let mut harness = Harness::new(plugin.clone());
+        let a = harness.spawn();
+        harness.frame(&[a], NONE);
+        harness.frame(&[a], &[tracked]);
+        assert_eq!(kinds(&harness.frame(&[a], LEFT_RIGHT)), ["Cancelled"]);
+        // Releasing the extra button resumes the press.
+        let transitions = harness.frame(&[a], &[tracked]);
+        assert_eq!(kinds(&transitions), ["Pressed"]);
+        assert_eq!(transitions[0].button(), Some(tracked));
+        harness.frame(&[a], NONE);
+
+        // Releasing the tracked button does not press the extra one.
+        harness.frame(&[a], &[tracked]);
+        harness.frame(&[a], LEFT_RIGHT);
+        assert!(harness.frame(&[a], &[other]).is_empty());
+        assert!(harness.machine().is_cancelled());
+        harness.frame(&[a], NONE);
+        assert!(!harness.machine().is_cancelled());
+    }
+}
+
+#[test]
+fn request_cancel_does_not_resume() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .resume_after_chord(true)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.machine_mut().request_cancel();
+    harness.frame(&[a], LEFT);
+    assert!(harness.frame(&[a], LEFT).is_empty());
+    assert!(harness.machine().is_cancelled());
+}
+
+#[test]
+fn paused_timers_freeze_long_press_and_hover() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .long_press_duration(Some(0.1))
+        .build();
+    let mut harness = Harness::new(plugin);
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], NONE);
+    assert!(harness.machine().hover_started.is_some());
+    harness.frame(&[a], LEFT);
+    harness.machine_mut().timers_paused = true;
+    for _ in 0..20 {
+        assert!(!kinds(&harness.frame(&[a], LEFT)).contains(&"LongPress"));
+    }
+    harness.machine_mut().timers_paused = false;
+    let fired = (0..20).any(|_| kinds(&harness.frame(&[a], LEFT)).contains(&"LongPress"));
+    assert!(fired);
+}
+
+#[test]
+fn hover_elapsed_restarts_on_each_hovered_entity() {
+    use bevy::time::{Time, Virtual};
+
+    let hover_elapsed = |harness: &Harness| {
+        let now = harness
+            .app
+            .world()
+            .resource::<Time<Virtual>>()
+            .elapsed_secs();
+        harness.machine().hover_elapsed(now)
+    };
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let (a, b) = (harness.spawn(), harness.spawn());
+    harness.frame(&[], NONE);
+    assert_eq!(hover_elapsed(&harness), None);
+    harness.frame(&[a], NONE);
+    assert_eq!(hover_elapsed(&harness), Some(0.0));
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], NONE);
+    let elapsed = hover_elapsed(&harness).unwrap();
+    assert!((elapsed - 0.032).abs() < 1e-4, "{elapsed}");
+    // Moving straight to another entity restarts it.
+    assert_eq!(
+        kinds(&harness.frame(&[b], NONE)),
+        ["HoverExit", "HoverEnter"]
+    );
+    assert_eq!(hover_elapsed(&harness), Some(0.0));
+    harness.frame(&[], NONE);
+    assert_eq!(hover_elapsed(&harness), None);
+}
+
+#[test]
+fn idle_time_grows_until_the_next_interaction() {
+    use bevy::time::{Time, Virtual};
+
+    let idle_time = |harness: &Harness| {
+        let now = harness
+            .app
+            .world()
+            .resource::<Time<Virtual>>()
+            .elapsed_secs();
+        harness.machine().idle_time(now)
+    };
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    assert_eq!(idle_time(&harness), 0.0);
+    let mut idle = 0.0;
+    for _ in 0..5 {
+        harness.frame(&[a], NONE);
+        assert!(idle_time(&harness) > idle);
+        idle = idle_time(&harness);
+    }
+    harness.frame_at(Vec2::new(5.0, 0.0), &[a], NONE);
+    assert_eq!(idle_time(&harness), 0.0);
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(idle_time(&harness), 0.0);
+}
+
+#[test]
+fn clock_follows_elapsed_time_minus_pauses() {
+    use bevy::time::{Time, Virtual};
+
+    let elapsed = |harness: &Harness| {
+        harness
+            .app
+            .world()
+            .resource::<Time<Virtual>>()
+            .elapsed_secs()
+    };
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    for _ in 0..5 {
+        harness.frame(&[], NONE);
+    }
+    assert_eq!(harness.machine().now, elapsed(&harness));
+    // A window opened later shares the clock.
+    let window = harness.app.world_mut().spawn(Window::default()).id();
+    harness.frame(&[], NONE);
+    let machines = harness.app.world().resource::<PickingStateMachines>();
+    assert_eq!(machines[&window].now, elapsed(&harness));
+    harness.machine_mut().timers_paused = true;
+    let paused = harness.machine().now;
+    for _ in 0..3 {
+        harness.frame(&[], NONE);
+        assert_eq!(harness.machine().now, paused);
+    }
+    harness.machine_mut().timers_paused = false;
+    harness.frame(&[], NONE);
+    let lag = elapsed(&harness) - harness.machine().now;
+    assert!((lag - 0.048).abs() < 1e-4, "{lag}");
+}
+
+#[cfg(feature = "highlight")]
+#[test]
+fn highlight_follows_hover_and_press() {
+    use crate::{HighlightKind, Highlighted, PickingPropagation};
+    use bevy::ecs::hierarchy::ChildOf;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let parent = harness.spawn();
+    let world = harness.app.world_mut();
+    let child = world.spawn(ChildOf(parent)).id();
+    let other = world.spawn_empty().id();
+    let button = world.spawn(PickingPropagation::PropagateUp(1)).id();
+    let sibling = world.spawn(ChildOf(other)).id();
+    world.entity_mut(button).insert(ChildOf(other));
+    let kind = |harness: &Harness, entity| {
+        harness
+            .app
+            .world()
+            .get::<Highlighted>(entity)
+            .map(|x| x.kind)
+    };
+    harness.frame(&[parent], NONE);
+    assert_eq!(kind(&harness, parent), Some(HighlightKind::Hover));
+    assert_eq!(kind(&harness, child), Some(HighlightKind::Hover));
+    harness.frame(&[parent], LEFT);
+    assert_eq!(kind(&harness, parent), Some(HighlightKind::Press));
+    assert_eq!(kind(&harness, child), Some(HighlightKind::Press));
+    harness.frame(&[parent], NONE);
+    assert_eq!(kind(&harness, parent), Some(HighlightKind::Hover));
+    // Propagating up reaches the siblings, the previous highlights are removed.
+    harness.frame(&[button], NONE);
+    assert_eq!(kind(&harness, parent), None);
+    assert_eq!(kind(&harness, child), None);
+    assert_eq!(kind(&harness, button), Some(HighlightKind::Hover));
+    assert_eq!(kind(&harness, sibling), Some(HighlightKind::Hover));
+    harness.frame(&[], NONE);
+    assert_eq!(kind(&harness, button), None);
+    assert_eq!(kind(&harness, sibling), None);
+}
+
+#[test]
+fn button_filters_match_the_press_path() {
+    use crate::{ButtonFilter, ButtonFilters, DefaultButtonFilter, ExtraButtons};
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let world = harness.app.world_mut();
+    let unfiltered = world.spawn_empty().id();
+    let filtered = world.spawn(ButtonFilter::new([MouseButton::Right])).id();
+    let extra = world.spawn(ExtraButtons::new([MouseButton::Middle])).id();
+    let would_press = |harness: &mut Harness| {
+        harness
+            .app
+            .world_mut()
+            .run_system_once(move |filters: ButtonFilters| {
+                [unfiltered, filtered, extra].map(|entity| {
+                    [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+                        .map(|button| filters.press_allowed(entity, button))
+                })
+            })
+            .unwrap()
+    };
+    // Allowed and unfiltered, denied by the filter, not allowed, and allowed by `ExtraButtons`.
+    assert_eq!(
+        would_press(&mut harness),
+        [
+            [true, false, false],
+            [false, false, false],
+            [true, false, true]
+        ]
+    );
+    harness
+        .app
+        .world_mut()
+        .insert_resource(DefaultButtonFilter(ButtonFilter::new([
+            MouseButton::Middle,
+        ])));
+    assert_eq!(
+        would_press(&mut harness),
+        [
+            [false, false, false],
+            [false, false, false],
+            [false, false, true]
+        ]
+    );
+    // Agrees with the state machine.
+    harness.frame(&[extra], NONE);
+    harness.frame(&[extra], &[MouseButton::Middle]);
+    assert!(harness.machine().is_pressing());
+}
+
+#[test]
+fn would_press_checks_allowed_buttons_then_the_filter() {
+    use crate::ButtonFilter;
+    use bevy::ecs::system::Res;
+
+    let plugin = PickingStateMachinePlugin::builder().allowed_button(MouseButton::Right);
+    let mut harness = Harness::new(plugin.build());
+    let world = harness.app.world_mut();
+    let unfiltered = world.spawn_empty().id();
+    let filtered = world.spawn(ButtonFilter::new([MouseButton::Right])).id();
+    let would_press = |harness: &mut Harness| {
+        harness
+            .app
+            .world_mut()
+            .run_system_once(
+                move |state_machine: Res<PickingStateMachine>, filters: Query<&ButtonFilter>| {
+                    [unfiltered, filtered].map(|entity| {
+                        [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+                            .map(|button| state_machine.would_press(entity, button, &filters))
+                    })
+                },
+            )
+            .unwrap()
+    };
+    harness.frame(&[], NONE);
+    // Allowed and unfiltered, allowed and filtered, denied and unfiltered, denied and filtered.
+    assert_eq!(
+        would_press(&mut harness),
+        [[true, true, false], [false, true, false]]
+    );
+    // Agrees with the state machine.
+    harness.frame(&[filtered], NONE);
+    harness.frame(&[filtered], LEFT);
+    assert!(!harness.machine().is_pressing());
+    harness.frame(&[filtered], NONE);
+    harness.frame(&[filtered], &[MouseButton::Right]);
+    assert!(harness.machine().is_pressing());
+    // Nothing is pressed while cancelled.
+    harness.frame(&[filtered], LEFT_RIGHT);
+    assert_eq!(would_press(&mut harness), [[false; 3]; 2]);
+}
+
+#[test]
+fn held_extra_button_hovers_until_released() {
+    use crate::ExtraButtons;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let gizmo = harness
+        .app
+        .world_mut()
+        .spawn(ExtraButtons::new([MouseButton::Middle]))
+        .id();
+    let middle = &[MouseButton::Middle];
+    harness.frame(&[], middle);
+    let mut transitions = Vec::new();
+    for _ in 0..5 {
+        transitions.extend(harness.frame(&[gizmo], middle));
+        assert!(!harness.machine().button_released);
+    }
+    assert_eq!(kinds(&transitions), ["HoverEnter"]);
+    assert!(!harness.machine().is_pressing());
+    // A fresh press over the entity is allowed.
+    harness.frame(&[gizmo], NONE);
+    assert_eq!(kinds(&harness.frame(&[gizmo], middle)), ["Pressed"]);
+}
+
+#[test]
+fn only_scripted_presses_are_synthetic() {
+    use bevy::input::gamepad::{Gamepad, RawGamepadButtonChangedEvent, RawGamepadEvent};
+
+    let mut harness = Harness::new(PickingStateMachinePlugin {
+        gamepad_confirm: Some(GamepadButton::South),
+        ..Default::default()
+    });
+    let a = harness.spawn();
+    let gamepad = harness.app.world_mut().spawn(Gamepad::default()).id();
+    let confirm = |harness: &mut Harness, value: f32| {
+        let event = RawGamepadButtonChangedEvent::new(gamepad, GamepadButton::South, value);
+        harness
+            .app
+            .world_mut()
+            .write_message(RawGamepadEvent::Button(event));
+        harness.frame(&[a], NONE)
+    };
+    harness.frame(&[a], NONE);
+    assert_eq!(kinds(&confirm(&mut harness, 1.0)), ["Pressed"]);
+    assert!(!harness.machine().was_synthetic());
+    assert_eq!(kinds(&confirm(&mut harness, 0.0)), ["Released"]);
+    assert!(!harness.machine().was_synthetic());
+    harness.machine_mut().keyboard_press();
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["Pressed"]);
+    assert!(harness.machine().was_synthetic());
+    assert!(harness.frame(&[a], NONE).is_empty());
+    assert!(!harness.machine().was_synthetic());
+}
+
+#[cfg(feature = "debug")]
+#[test]
+fn debug_press_key_is_not_synthetic() {
+    use crate::DebugPressKey;
+    use bevy::input::{
+        ButtonState,
+        keyboard::{Key, KeyCode, KeyboardInput},
+    };
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    harness.app.insert_resource(DebugPressKey(KeyCode::Space));
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.app.world_mut().write_message(KeyboardInput {
+        key_code: KeyCode::Space,
+        logical_key: Key::Space,
+        state: ButtonState::Pressed,
+        text: None,
+        repeat: false,
+        window: Entity::PLACEHOLDER,
+    });
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["Pressed"]);
+    assert!(!harness.machine().was_synthetic());
+}
+
+#[test]
+fn suppression_exits_and_releases() {
+    use crate::{PickingStateMachineSystems, suppress_picking};
+    use bevy::ecs::{
+        resource::Resource,
+        system::{IntoSystem, Res},
+    };
+
+    /// Stands in for `wants_pointer_input` of `egui`.
+    #[derive(Resource)]
+    struct UiWantsPointer(bool);
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    harness.app.insert_resource(UiWantsPointer(false));
+    harness.app.add_systems(
+        PreUpdate,
+        (|ui: Res<UiWantsPointer>| ui.0)
+            .pipe(suppress_picking)
+            .before(PickingStateMachineSystems::Resolve),
+    );
+    let wants_pointer = |harness: &mut Harness, value: bool| {
+        harness.app.world_mut().resource_mut::<UiWantsPointer>().0 = value;
+    };
+    let a = harness.spawn();
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["HoverEnter"]);
+    wants_pointer(&mut harness, true);
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["HoverExit"]);
+    assert!(harness.frame(&[a], LEFT).is_empty());
+    assert_eq!(harness.machine().current, GlobalPickingState::None);
+    wants_pointer(&mut harness, false);
+    harness.frame(&[a], NONE);
+    assert_eq!(kinds(&harness.frame(&[a], LEFT)), ["Pressed"]);
+    wants_pointer(&mut harness, true);
+    let transitions = harness.frame(&[a], LEFT);
+    assert_eq!(kinds(&transitions), ["ReleasedOutside", "HoverExit"]);
+    assert_eq!(harness.machine().current, GlobalPickingState::None);
+}
+
+#[test]
+fn observers_receive_transitions_on_their_entity() {
+    use bevy::ecs::{event::EntityEvent, observer::On, resource::Resource, system::ResMut};
+
+    #[derive(Default, Resource)]
+    struct Observed(Vec<(Entity, PickingTransition)>, Vec<PickingTransition>);
+
+    let mut harness = Harness::new(PickingStateMachinePlugin {
+        emit_events: true,
+        ..Default::default()
+    });
+    harness.app.init_resource::<Observed>();
+    harness.app.add_observer(
+        |event: On<PickingTransition>, mut observed: ResMut<Observed>| {
+            observed.0.push((event.event_target(), event.clone()));
+        },
+    );
+    let a = harness.spawn();
+    let b = harness.spawn();
+    harness.app.world_mut().entity_mut(b).observe(
+        |event: On<PickingTransition>, mut observed: ResMut<Observed>| {
+            observed.1.push(event.clone());
+        },
+    );
+    let mut expected = Vec::new();
+    for hits in [&[a][..], &[b], &[]] {
+        expected.extend(harness.frame(hits, NONE));
+    }
+    let observed = harness.app.world().resource::<Observed>();
+    assert_eq!(
+        kinds(&expected),
+        ["HoverEnter", "HoverExit", "HoverEnter", "HoverExit"]
+    );
+    assert_eq!(
+        observed.0,
+        expected
+            .iter()
+            .map(|x| (x.entity(), x.clone()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(kinds(&observed.1), ["HoverEnter", "HoverExit"]);
+    assert!(observed.1.iter().all(|x| x.entity() == b));
+}
+
+#[test]
+fn transition_messages_are_written_without_emit_events() {
+    use crate::PickingTransitionEvent;
+    use bevy::{
+        app::Update,
+        ecs::{message::MessageReader, resource::Resource, system::ResMut},
+    };
+
+    #[derive(Default, Resource)]
+    struct Read(Vec<PickingTransitionEvent>, Vec<PickingTransition>);
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    harness.app.init_resource::<Read>();
+    harness.app.add_systems(
+        Update,
+        |mut events: MessageReader<PickingTransitionEvent>,
+         mut transitions: MessageReader<PickingTransition>,
+         mut read: ResMut<Read>| {
+            read.0.extend(events.read().cloned());
+            read.1.extend(transitions.read().cloned());
+        },
+    );
+    let a = harness.spawn();
+    let mut expected = Vec::new();
+    for (hits, buttons) in [(&[a][..], NONE), (&[a], LEFT), (&[a], NONE), (&[], NONE)] {
+        expected.extend(harness.frame(hits, buttons));
+    }
+    assert_eq!(
+        kinds(&expected),
+        ["HoverEnter", "Pressed", "Released", "Clicked", "HoverExit"]
+    );
+    let read = harness.app.world().resource::<Read>();
+    assert_eq!(read.1, expected);
+    assert!(
+        read.0
+            .iter()
+            .zip(&expected)
+            .all(|(event, x)| event.entity == x.entity() && event.transition == *x)
+    );
+    assert_eq!(read.0.len(), expected.len());
+}
+
+#[test]
+fn drag_axis_locks_to_the_first_dominant_axis() {
+    use crate::DragAxis;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(harness.machine().locked_drag_axis(8.0), None);
+    harness.frame_at(Vec2::new(10.0, 1.0), &[a], LEFT);
+    assert_eq!(
+        harness.machine().locked_drag_axis(8.0),
+        Some(DragAxis::Horizontal)
+    );
+    // Later vertical movement keeps the lock, a larger threshold locks later.
+    harness.frame_at(Vec2::new(12.0, 6.0), &[a], LEFT);
+    harness.frame_at(Vec2::new(12.0, 30.0), &[a], LEFT);
+    assert_eq!(
+        harness.machine().locked_drag_axis(8.0),
+        Some(DragAxis::Horizontal)
+    );
+    assert_eq!(
+        harness.machine().locked_drag_axis(20.0),
+        Some(DragAxis::Vertical)
+    );
+    assert_eq!(harness.machine().locked_drag_axis(40.0), None);
+    // Unlocked by the next press.
+    harness.frame(&[a], NONE);
+    assert_eq!(harness.machine().locked_drag_axis(8.0), None);
+    harness.frame(&[a], LEFT);
+    harness.frame_at(Vec2::new(12.0, 45.0), &[a], LEFT);
+    assert_eq!(
+        harness.machine().locked_drag_axis(8.0),
+        Some(DragAxis::Vertical)
+    );
+}
+
+#[test]
+fn multi_click_interval_is_measured_between_releases() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .multi_click_interval(0.05)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let a = harness.spawn();
+    let click_count = |harness: &mut Harness, held: usize| {
+        for _ in 0..held {
+            harness.frame(&[a], LEFT);
+        }
+        let transitions = harness.frame(&[a], NONE);
+        transitions.iter().find_map(|x| match x {
+            PickingTransition::Clicked { count, .. } => Some(*count),
+            _ => None,
+        })
+    };
+    harness.frame(&[a], NONE);
+    assert_eq!(click_count(&mut harness, 1), Some(1));
+    // Released 48ms after the previous release.
+    assert_eq!(click_count(&mut harness, 2), Some(2));
+    // Pressed 16ms after the previous release, but released 64ms after it.
+    assert_eq!(click_count(&mut harness, 3), Some(1));
+    // Released 48ms after it, after waiting 16ms.
+    harness.frame(&[a], NONE);
+    assert_eq!(click_count(&mut harness, 1), Some(2));
+}
+
+#[test]
+fn hit_updates_carry_the_hit_of_the_pressed_entity() {
+    use bevy::math::Vec3;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin {
+        emit_hit_updates: true,
+        ..Default::default()
+    });
+    let a = harness.spawn();
+    let frame = |harness: &mut Harness, x: f32, buttons: &[MouseButton]| {
+        let hit = HitData::new(harness.camera, 1.0, Some(Vec3::new(x, 0.0, 0.0)), None);
+        let hits = PointerHits::new(PointerId::Mouse, vec![(a, hit)], 0.0);
+        harness.app.world_mut().write_message(hits);
+        harness
+            .frame(&[], buttons)
+            .into_iter()
+            .filter_map(|x| match x {
+                PickingTransition::HitUpdate { entity, hit } => Some((entity, hit.position)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    // Only while pressed, and only when the hit changes.
+    assert!(frame(&mut harness, 0.0, NONE).is_empty());
+    assert_eq!(
+        frame(&mut harness, 0.0, LEFT),
+        [(a, Some(Vec3::new(0.0, 0.0, 0.0)))]
+    );
+    assert!(frame(&mut harness, 0.0, LEFT).is_empty());
+    assert_eq!(
+        frame(&mut harness, 2.0, LEFT),
+        [(a, Some(Vec3::new(2.0, 0.0, 0.0)))]
+    );
+    assert!(frame(&mut harness, 3.0, NONE).is_empty());
+}
+
+#[test]
+fn pick_reactions_reach_the_affected_entities() {
+    use crate::{PickReactive, PickReactivePlugin};
+    use bevy::ecs::component::Component;
+
+    #[derive(Component, Default)]
+    struct PressCounter(usize);
+
+    impl PickReactive for PressCounter {
+        fn on_press(&mut self, _: MouseButton) {
+            self.0 += 1;
+        }
+    }
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    harness
+        .app
+        .add_plugins(PickReactivePlugin::<PressCounter>::default());
+    let world = harness.app.world_mut();
+    let parent = world.spawn(PressCounter::default()).id();
+    let child = world.spawn((PressCounter::default(), ChildOf(parent))).id();
+    let other = world.spawn(PressCounter::default()).id();
+    let counts = |harness: &Harness| {
+        [parent, child, other]
+            .map(|entity| harness.app.world().get::<PressCounter>(entity).unwrap().0)
+    };
+    harness.frame(&[parent], NONE);
+    harness.frame(&[parent], LEFT);
+    assert_eq!(counts(&harness), [1, 1, 0]);
+    harness.frame(&[child], NONE);
+    harness.frame(&[child], NONE);
+    harness.frame(&[child], LEFT);
+    // Presses propagate down, not up.
+    assert_eq!(counts(&harness), [1, 2, 0]);
+    harness.frame(&[other], NONE);
+    harness.frame(&[other], NONE);
+    harness.frame(&[other], LEFT);
+    assert_eq!(counts(&harness), [1, 2, 1]);
+}
+
+#[test]
+fn drags_are_opt_in_and_stay_on_their_entity() {
+    // Without a threshold, moving presses still click.
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.frame_at(Vec2::new(100.0, 0.0), &[a], LEFT);
+    assert!(!harness.machine().is_dragging());
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["Released", "Clicked"]);
+
+    let plugin = PickingStateMachinePlugin::builder()
+        .drag_threshold(4.0)
+        .acquire_while_pressed(true)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let (a, b) = (harness.spawn(), harness.spawn());
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.frame_at(Vec2::new(10.0, 0.0), &[a], LEFT);
+    assert_eq!(
+        harness.machine().current,
+        GlobalPickingState::Dragging { entity: a }
+    );
+    harness.frame_at(Vec2::new(20.0, 0.0), &[b], LEFT);
+    assert_eq!(
+        harness.machine().current,
+        GlobalPickingState::Dragging { entity: a }
+    );
+}
+
+#[test]
+fn commit_and_abort_drag() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .drag_threshold(4.0)
+        .build();
+    let drag = |hits: &[Entity]| {
+        let mut harness = Harness::new(plugin.clone());
+        let a = harness.spawn();
+        harness.frame(&[a], NONE);
+        harness.frame(&[a], LEFT);
+        harness.frame_at(Vec2::new(10.0, 0.0), &[a], LEFT);
+        assert!(harness.machine().is_dragging());
+        let hits = hits.iter().map(|_| a).collect::<Vec<_>>();
+        (harness, a, hits)
+    };
+
+    let (mut harness, a, hits) = drag(&[Entity::PLACEHOLDER]);
+    harness.machine_mut().commit_drag();
+    let transitions = harness.frame(&hits, LEFT);
+    assert_eq!(kinds(&transitions), ["DragEnd", "Released"]);
+    assert!(harness.machine().was_synthetic());
+    assert_eq!(
+        harness.machine().current,
+        GlobalPickingState::Hover { entity: a }
+    );
+    // The held button is ignored until released.
+    assert!(!kinds(&harness.frame(&hits, LEFT)).contains(&"Pressed"));
+    harness.frame(&hits, NONE);
+    assert!(!harness.machine().is_cancelled());
+    assert_eq!(kinds(&harness.frame(&hits, LEFT)), ["Pressed"]);
+
+    // Committed away from the entity, like a release outside.
+    let (mut harness, _, _) = drag(&[]);
+    harness.machine_mut().commit_drag();
+    let transitions = harness.frame(&[], LEFT);
+    assert_eq!(
+        kinds(&transitions),
+        ["DragEnd", "ReleasedOutside", "HoverExit"]
+    );
+
+    let (mut harness, _, hits) = drag(&[Entity::PLACEHOLDER]);
+    harness.machine_mut().abort_drag();
+    let transitions = harness.frame(&hits, LEFT);
+    assert_eq!(kinds(&transitions), ["DragEnd", "Cancelled"]);
+    assert!(!kinds(&harness.frame(&hits, LEFT)).contains(&"Pressed"));
+    harness.frame(&hits, NONE);
+    assert!(!harness.machine().is_cancelled());
+}
+
+#[test]
+fn windows_have_their_own_machines() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let (a, b) = (harness.spawn(), harness.spawn());
+    let world = harness.app.world_mut();
+    world.spawn((Window::default(), PrimaryWindow));
+    let window = world.spawn(Window::default()).id();
+    let camera = world
+        .spawn(RenderTarget::Window(WindowRef::Entity(window)))
+        .id();
+    let hit = HitData::new(camera, 0.0, None, None);
+    world.write_message(PointerHits::new(PointerId::Mouse, vec![(b, hit)], 0.0));
+    // Hits of the harness camera, which renders nowhere, go to the primary window.
+    assert_eq!(kinds(&harness.frame(&[a], NONE)), ["HoverEnter"]);
+    assert_eq!(
+        harness.machine().current,
+        GlobalPickingState::Hover { entity: a }
+    );
+    let machines = harness.app.world().resource::<PickingStateMachines>();
+    assert_eq!(machines.len(), 1);
+    assert_eq!(
+        machines[&window].current,
+        GlobalPickingState::Hover { entity: b }
+    );
+    harness.app.world_mut().despawn(window);
+    harness.frame(&[a], NONE);
+    assert!(
+        harness
+            .app
+            .world()
+            .resource::<PickingStateMachines>()
+            .is_empty()
+    );
+}
+
+#[test]
+fn every_window_is_dispatched() {
+    use crate::AllPickingStateMachines;
+    use bevy::{
+        app::Update,
+        ecs::{message::MessageReader, resource::Resource, system::ResMut},
+    };
+
+    #[derive(Default, Resource)]
+    struct Read(Vec<PickingTransition>);
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    harness.app.init_resource::<Read>();
+    harness.app.add_systems(
+        Update,
+        |mut transitions: MessageReader<PickingTransition>, mut read: ResMut<Read>| {
+            read.0.extend(transitions.read().cloned());
+        },
+    );
+    let (a, b) = (harness.spawn(), harness.spawn());
+    let world = harness.app.world_mut();
+    let primary = world.spawn((Window::default(), PrimaryWindow)).id();
+    let window = world.spawn(Window::default()).id();
+    let camera = world
+        .spawn(RenderTarget::Window(WindowRef::Entity(window)))
+        .id();
+    let hit = HitData::new(camera, 0.0, None, None);
+    world.write_message(PointerHits::new(PointerId::Mouse, vec![(b, hit)], 0.0));
+    harness.frame(&[a], NONE);
+    assert_eq!(
+        harness.app.world().resource::<Read>().0,
+        [
+            PickingTransition::HoverEnter { entity: a },
+            PickingTransition::HoverEnter { entity: b },
+        ]
+    );
+    let current = harness
+        .app
+        .world_mut()
+        .run_system_once(move |machines: AllPickingStateMachines| {
+            [primary, window, a].map(|x| machines.get(x).map(|x| x.current))
+        })
+        .unwrap();
+    assert_eq!(
+        current,
+        [
+            Some(GlobalPickingState::Hover { entity: a }),
+            Some(GlobalPickingState::Hover { entity: b }),
+            None,
+        ]
+    );
+    #[cfg(feature = "highlight")]
+    for entity in [a, b] {
+        assert!(
+            harness
+                .app
+                .world()
+                .get::<crate::Highlighted>(entity)
+                .is_some()
+        );
+    }
+}
+
+#[test]
+fn retained_drags_keep_one_lifecycle() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .drag_threshold(4.0)
+        .press_retention(PressRetention::ReleaseWhileOutside)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert!(kinds(&harness.frame_at(Vec2::new(10.0, 0.0), &[a], LEFT)).contains(&"DragStart"));
+    assert_eq!(
+        kinds(&harness.frame_at(Vec2::new(20.0, 0.0), &[], LEFT)),
+        ["DragOutside"]
+    );
+    let transitions = kinds(&harness.frame_at(Vec2::new(10.0, 0.0), &[a], LEFT));
+    assert_eq!(transitions[0], "DragReturn");
+    assert!(!transitions.contains(&"DragStart") && !transitions.contains(&"Pressed"));
+    assert!(harness.machine().is_dragging());
+    harness.frame_at(Vec2::new(20.0, 0.0), &[], LEFT);
+    assert_eq!(
+        kinds(&harness.frame(&[], NONE)),
+        ["DragEnd", "ReleasedOutside"]
+    );
+}
+
+#[test]
+fn retained_press_ends_when_another_entity_is_pressed() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .acquire_while_pressed(true)
+        .press_retention(PressRetention::ReleaseWhileOutside)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let (a, b) = (harness.spawn(), harness.spawn());
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(kinds(&harness.frame(&[], LEFT)), ["DragOutside"]);
+    let transitions = harness.frame(&[b], LEFT);
+    assert_eq!(kinds(&transitions), ["ReleasedOutside", "Pressed"]);
+    assert_eq!(transitions[0].entity(), a);
+    // Only `b` is retained now.
+    assert_eq!(kinds(&harness.frame(&[], LEFT)), ["DragOutside"]);
+    let transitions = harness.frame(&[a], NONE);
+    assert_eq!(transitions[0].entity(), b);
+    assert_eq!(kinds(&transitions), ["ReleasedOutside", "HoverEnter"]);
+}
+
+/// Active flags of `entities`, the deepest active entity and the routed handlers,
+/// with and without [`PropagationCache`].
+fn propagation_results(harness: &mut Harness, entities: &[Entity]) -> Vec<bool> {
+    let entities = entities.to_vec();
+    let (cached, uncached) = harness
+        .app
+        .world_mut()
+        .run_system_once(
+            move |mut propagated: PropagatedPickingStateMachine,
+                  handlers: Query<Entity, With<PickHandler>>| {
+                let mut results = Vec::new();
+                for _ in 0..2 {
+                    let active: Vec<_> = entities
+                        .iter()
+                        .map(|x| propagated.get_state(*x) != EntityPickingState::None)
+                        .collect();
+                    let deepest = propagated.deepest_active(entities.iter().copied());
+                    let routed: Vec<_> = propagated.routed_transitions(&handlers).collect();
+                    results.push((active, deepest, routed));
+                    propagated.cache = None;
+                }
+                (results.remove(0), results.remove(0))
+            },
+        )
+        .unwrap();
+    assert_eq!(cached, uncached);
+    cached.0
+}
+
+#[test]
+fn propagation_cache_matches_the_hierarchy() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    harness.app.insert_resource(PropagationCache::new(2));
+    let world = harness.app.world_mut();
+    // A deep chain with a side branch on each entity, deeper than the cache.
+    let mut chain = vec![world.spawn(PickHandler).id()];
+    for i in 1..8 {
+        chain.push(world.spawn(ChildOf(chain[i - 1])).id());
+    }
+    let branches: Vec<_> = chain
+        .iter()
+        .map(|x| world.spawn(ChildOf(*x)).id())
+        .collect();
+    let leaf = chain[7];
+    world
+        .entity_mut(leaf)
+        .insert(PickingPropagation::PropagateUp(2));
+    let entities: Vec<_> = chain.iter().chain(&branches).copied().collect();
+    let results = |harness: &mut Harness| {
+        harness.frame(&[], NONE);
+        harness.frame(&[leaf], NONE);
+        let active = propagation_results(harness, &entities);
+        entities
+            .iter()
+            .zip(active)
+            .filter_map(|(entity, active)| active.then_some(*entity))
+            .collect::<Vec<_>>()
+    };
+    let active = results(&mut harness);
+    assert!(active.contains(&chain[6]) && active.contains(&branches[5]));
+    // Move the subtree, the cached chain of `leaf` must follow.
+    harness
+        .app
+        .world_mut()
+        .entity_mut(chain[6])
+        .insert(ChildOf(branches[2]));
+    let active = results(&mut harness);
+    assert!(active.contains(&chain[6]) && !active.contains(&branches[5]));
+    // `PropagateUp` stops at the `PickRoot`.
+    harness
+        .app
+        .world_mut()
+        .entity_mut(chain[6])
+        .insert(PickRoot);
+    assert_eq!(results(&mut harness), [chain[7], branches[6], branches[7]]);
+    harness
+        .app
+        .world_mut()
+        .entity_mut(chain[6])
+        .remove::<(ChildOf, PickRoot)>();
+    results(&mut harness);
+}
+
+#[test]
+fn active_group_rect_is_the_union_of_the_group() {
+    use crate::ActiveGroupBounds;
+    use bevy::{
+        camera::{
+            Camera, CameraProjection, ComputedCameraValues, OrthographicProjection,
+            RenderTargetInfo, primitives::Aabb,
+        },
+        math::{Rect, UVec2, Vec3},
+        transform::components::GlobalTransform,
+    };
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let mut projection = OrthographicProjection::default_2d();
+    projection.update(800.0, 600.0);
+    let camera = Camera {
+        computed: ComputedCameraValues {
+            clip_from_view: projection.get_clip_from_view(),
+            target_info: Some(RenderTargetInfo {
+                physical_size: UVec2::new(800, 600),
+                scale_factor: 1.0,
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let rect = |x: f32, y: f32, half: f32| {
+        (
+            GlobalTransform::from_translation(Vec3::new(x, y, 0.0)),
+            Aabb::from_min_max(Vec3::splat(-half), Vec3::splat(half)),
+        )
+    };
+    let world = harness.app.world_mut();
+    world
+        .entity_mut(harness.camera)
+        .insert((camera, GlobalTransform::IDENTITY));
+    let parent = world.spawn(rect(0.0, 0.0, 10.0)).id();
+    world.spawn((rect(100.0, 50.0, 5.0), ChildOf(parent)));
+    // Skipped without bounds.
+    world.spawn((GlobalTransform::IDENTITY, ChildOf(parent)));
+    let unrelated = world.spawn(rect(-200.0, 0.0, 10.0)).id();
+    let group_rect = |harness: &mut Harness| {
+        harness
+            .app
+            .world_mut()
+            .run_system_once(|bounds: ActiveGroupBounds| bounds.active_group_rect())
+            .unwrap()
+    };
+    let close = |a: Rect, b: Rect| a.min.distance(b.min) < 1e-3 && a.max.distance(b.max) < 1e-3;
+    harness.frame(&[], NONE);
+    assert_eq!(group_rect(&mut harness), None);
+    harness.frame(&[parent], NONE);
+    let rect = group_rect(&mut harness).unwrap();
+    assert!(
+        close(rect, Rect::new(390.0, 245.0, 505.0, 310.0)),
+        "{rect:?}"
+    );
+    harness.frame(&[unrelated], NONE);
+    let rect = group_rect(&mut harness).unwrap();
+    assert!(
+        close(rect, Rect::new(190.0, 290.0, 210.0, 310.0)),
+        "{rect:?}"
+    );
+}
+
+#[test]
+fn active_group_rect_without_transforms_is_none() {
+    use crate::ActiveGroupBounds;
+    use bevy::{camera::primitives::Aabb, math::Vec3};
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness
+        .app
+        .world_mut()
+        .spawn(Aabb::from_min_max(Vec3::ZERO, Vec3::ONE))
+        .id();
+    harness.frame(&[a], NONE);
+    // Neither the entity nor the camera has a `GlobalTransform`.
+    let rect = harness
+        .app
+        .world_mut()
+        .run_system_once(|bounds: ActiveGroupBounds| bounds.active_group_rect())
+        .unwrap();
+    assert_eq!(rect, None);
+}
+
+/// Run with `cargo test --release -- --ignored --nocapture propagation_cache_timing`.
+#[test]
+#[ignore = "benchmark"]
+fn propagation_cache_timing() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    harness.app.insert_resource(PropagationCache::new(64));
+    let world = harness.app.world_mut();
+    // A forest of chains 100 deep.
+    let mut entities = vec![world.spawn_empty().id()];
+    for i in 1..10_000 {
+        let entity = match i % 100 {
+            0 => world.spawn_empty().id(),
+            _ => world.spawn(ChildOf(entities[i - 1])).id(),
+        };
+        entities.push(entity);
+    }
+    let (root, leaf) = (entities[0], entities[9_999]);
+    world
+        .entity_mut(leaf)
+        .insert(PickingPropagation::PropagateUp(50));
+    let start = std::time::Instant::now();
+    harness.frame(&[leaf], NONE);
+    println!("build: {:?}", start.elapsed());
+    let moved = entities[5_050];
+    harness
+        .app
+        .world_mut()
+        .run_system_once(move |mut propagated: PropagatedPickingStateMachine| {
+            for name in ["cached", "uncached"] {
+                let start = std::time::Instant::now();
+                let active = entities
+                    .iter()
+                    .filter(|x| propagated.get_state(**x) != EntityPickingState::None)
+                    .count();
+                println!("{name}: {:?}, {active} active", start.elapsed());
+                propagated.cache = None;
+            }
+        })
+        .unwrap();
+    // Moving one chain only updates that chain.
+    harness
+        .app
+        .world_mut()
+        .entity_mut(moved)
+        .insert(ChildOf(root));
+    let start = std::time::Instant::now();
+    harness.frame(&[leaf], NONE);
+    println!("update: {:?}", start.elapsed());
+}
+
+#[test]
+fn throttled_frames_keep_the_last_resolved_hits() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .resolve_interval(0.1)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let a = harness.spawn();
+    // The first frame is within the interval of the start.
+    for _ in 0..10 {
+        harness.frame(&[a], NONE);
+    }
+    assert!(harness.machine().pointer_over_interactive());
+    assert_eq!(harness.machine().shadow_hover(), Some(a));
+    // Until the next resolve the hover is kept, and so are the hits that acquired it.
+    let mut resolved = false;
+    for _ in 0..10 {
+        harness.frame(&[], NONE);
+        let machine = harness.machine();
+        let hovered = machine.current == GlobalPickingState::Hover { entity: a };
+        assert_eq!(machine.pointer_over_interactive(), hovered);
+        assert_eq!(machine.shadow_hover(), hovered.then_some(a));
+        resolved |= !hovered;
+    }
+    assert!(resolved);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn transitions_and_states_round_trip_through_serde() {
+    use bevy::math::Vec3;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    let mut transitions = Vec::new();
+    for (hits, buttons) in [(&[a][..], NONE), (&[a], LEFT), (&[a], NONE), (&[], NONE)] {
+        transitions.extend(harness.frame(hits, buttons));
+    }
+    let hit = HitData::new(harness.camera, 1.0, Some(Vec3::X), Some(Vec3::Z));
+    transitions.push(PickingTransition::HitUpdate { entity: a, hit });
+    let json = serde_json::to_string(&transitions).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Vec<PickingTransition>>(&json).unwrap(),
+        transitions
+    );
+    let state = GlobalPickingState::Pressed { entity: a };
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(
+        serde_json::from_str::<GlobalPickingState>(&json).unwrap(),
+        state
+    );
+}
+
+#[test]
+fn builder_sets_every_field() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .allowed_buttons([MouseButton::Right])
+        .allowed_button(MouseButton::Middle)
+        .cancel_hover(true)
+        .multi_button_policy(MultiButtonPolicy::FirstWins)
+        .acquire_while_pressed(true)
+        .press_acquire_on_spawn(true)
+        .pointer_origin(PointerOrigin::BottomLeft)
+        .within_batch_tiebreak(HitTiebreak::LastHit)
+        .prefer_current_on_tie(false)
+        .emit_hit_updates(true)
+        .prediction_time(1.0)
+        .resolve_interval(2.0)
+        .multi_click_interval(3.0)
+        .multi_click_radius(4.5)
+        .resume_after_chord(true)
+        .release_debounce(5.0)
+        .release_debounce_radius(6.0)
+        .skip_when_quiescent(true)
+        .rehover_on_release(true)
+        .emit_events(true)
+        .drag_threshold(8.0)
+        .drop_dwell(9.0)
+        .max_hit_batches(Some(10))
+        .long_press_duration(Some(11.0))
+        .respect_window_focus(true)
+        .press_retention(PressRetention::ReleaseWhileOutside)
+        .despawn_resets(false)
+        .route_scroll(true)
+        .line_to_pixel(12.0)
+        .gamepad_confirm(Some(GamepadButton::South))
+        .build();
+    let literal = PickingStateMachinePlugin {
+        allowed_buttons: vec![MouseButton::Right, MouseButton::Middle],
+        cancel_hover: true,
+        multi_button_policy: MultiButtonPolicy::FirstWins,
+        acquire_while_pressed: true,
+        press_acquire_on_spawn: true,
+        pointer_origin: PointerOrigin::BottomLeft,
+        within_batch_tiebreak: HitTiebreak::LastHit,
+        prefer_current_on_tie: false,
+        emit_hit_updates: true,
+        prediction_time: 1.0,
+        resolve_interval: 2.0,
+        multi_click_interval: 3.0,
+        multi_click_radius: 4.5,
+        resume_after_chord: true,
+        release_debounce: 5.0,
+        release_debounce_radius: 6.0,
+        skip_when_quiescent: true,
+        rehover_on_release: true,
+        emit_events: true,
+        drag_threshold: 8.0,
+        drop_dwell: 9.0,
+        max_hit_batches: Some(10),
+        long_press_duration: Some(11.0),
+        respect_window_focus: true,
+        press_retention: PressRetention::ReleaseWhileOutside,
+        despawn_resets: false,
+        route_scroll: true,
+        line_to_pixel: 12.0,
+        gamepad_confirm: Some(GamepadButton::South),
+    };
+    assert_eq!(plugin, literal);
+    assert_eq!(
+        PickingStateMachinePlugin::builder().build(),
+        PickingStateMachinePlugin::default()
+    );
+}
+
+#[cfg(feature = "channel")]
+#[test]
+fn transition_channel_sends_every_transition_to_every_subscriber() {
+    use bevy::tasks::{AsyncComputeTaskPool, block_on};
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let channel = crate::TransitionChannel::new(8);
+    let receivers = [channel.subscribe(), channel.subscribe()];
+    harness.app.insert_resource(channel);
+    let a = harness.spawn();
+    let tasks = receivers.map(|receiver| {
+        AsyncComputeTaskPool::get().spawn(async move {
+            let mut transitions = Vec::new();
+            while let Ok(transition) = receiver.recv().await {
+                transitions.push(transition);
+                if transitions.len() == 2 {
+                    break;
+                }
+            }
+            transitions
+        })
+    });
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    for task in tasks {
+        assert_eq!(kinds(&block_on(task)), ["HoverEnter", "Pressed"]);
+    }
+}
+
+#[test]
+fn get_state_of_each_global_state() {
+    let (a, b) = (
+        Entity::from_raw_u32(1).unwrap(),
+        Entity::from_raw_u32(2).unwrap(),
+    );
+    let mut state_machine = PickingStateMachine::default();
+    assert_eq!(state_machine.get_state(a), EntityPickingState::None);
+    for (current, expected) in [
+        (
+            GlobalPickingState::Hover { entity: a },
+            EntityPickingState::Hover,
+        ),
+        (
+            GlobalPickingState::Pressed { entity: a },
+            EntityPickingState::Pressed,
+        ),
+        (
+            GlobalPickingState::Dragging { entity: a },
+            EntityPickingState::Pressed,
+        ),
+    ] {
+        state_machine.current = current;
+        assert_eq!(state_machine.get_state(a), expected);
+        assert_eq!(state_machine.get_state(b), EntityPickingState::None);
+    }
+}
+
+#[test]
+fn despawning_the_pressed_entity_loses_the_press() {
+    use crate::EndReason;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.app.world_mut().despawn(a);
+    let transitions = harness.frame(&[], LEFT);
+    assert_eq!(kinds(&transitions), ["Cancelled", "Lost"]);
+    let ended = harness.machine().press_ended().unwrap();
+    assert_eq!(ended.entity, a);
+    assert_eq!(ended.reason, EndReason::LostTarget);
+}
+
+#[test]
+fn soak_never_references_despawned_entities() {
+    let mut plugins = permutation_plugins();
+    plugins.push(
+        PickingStateMachinePlugin::builder()
+            .drag_threshold(4.0)
+            .press_retention(PressRetention::ReleaseWhileOutside)
+            .press_acquire_on_spawn(true)
+            .build(),
+    );
+    for plugin in plugins {
+        let mut harness = Harness::new(plugin);
+        let mut alive: Vec<Entity> = Vec::new();
+        let mut despawned: Vec<Entity> = Vec::new();
+        let mut seed = 0x9e37_79b9_u32;
+        let mut random = move |n: usize| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as usize % n.max(1)
+        };
+        for frame in 0..500 {
+            if alive.len() < 2 || random(4) == 0 {
+                alive.push(harness.spawn());
+            }
+            // Hits are written before the despawns, like a backend running earlier in the frame.
+            let hits: Vec<_> = (0..random(3)).map(|_| alive[random(alive.len())]).collect();
+            if random(5) == 0 {
+                let entity = alive.swap_remove(random(alive.len()));
+                harness.app.world_mut().despawn(entity);
+                despawned.push(entity);
+            }
+            let known: Vec<_> = alive.iter().chain(&despawned).copied().collect();
+            match random(12) {
+                0 => harness
+                    .machine_mut()
+                    .keyboard_hover(known[random(known.len())]),
+                1 => harness.machine_mut().keyboard_press(),
+                2 => harness.machine_mut().keyboard_release(),
+                _ => (),
+            }
+            let buttons = [NONE, LEFT, LEFT, LEFT_RIGHT][random(4)];
+            let pointer = Vec2::new((frame % 5) as f32 * 3.0, 0.0);
+            let transitions = harness.frame_at(pointer, &hits, buttons);
+            let machine = harness.machine();
+            let exists = |entity: Entity| alive.contains(&entity);
+            assert!(
+                machine.current.current_entity().is_none_or(exists),
+                "{frame}"
+            );
+            assert!(machine.drag_over().is_none_or(exists), "{frame}");
+            assert!(machine.shadow_hover().is_none_or(exists), "{frame}");
+            // Only `Lost`, after cancelling its press, reports an entity despawned before its frame.
+            let lost = |entity: Entity| {
+                transitions
+                    .iter()
+                    .any(|x| matches!(x, PickingTransition::Lost { entity: e, .. } if *e == entity))
+            };
+            for transition in &transitions {
+                match transition {
+                    PickingTransition::Lost { entity, .. } => {
+                        assert!(despawned.contains(entity), "{frame}: {transition:?}")
+                    }
+                    PickingTransition::Cancelled { entity, .. } if lost(*entity) => (),
+                    _ => assert!(exists(transition.entity()), "{frame}: {transition:?}"),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn interaction_feedback_fires_on_a_click() {
+    use crate::InteractionFeedback;
+    use std::sync::{Arc, Mutex};
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let (press, release, hover) = (calls.clone(), calls.clone(), calls.clone());
+    harness.app.insert_resource(
+        InteractionFeedback::default()
+            .on_press(move |entity, button| {
+                press.lock().unwrap().push(("press", entity, Some(button)))
+            })
+            .on_release(move |entity, button| {
+                release
+                    .lock()
+                    .unwrap()
+                    .push(("release", entity, Some(button)))
+            })
+            .on_hover(move |entity| hover.lock().unwrap().push(("hover", entity, None))),
+    );
+    for (hits, buttons) in [(&[a][..], NONE), (&[a], LEFT), (&[a], NONE), (&[], NONE)] {
+        harness.frame(hits, buttons);
+    }
+    let left = Some(MouseButton::Left);
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [("hover", a, None), ("press", a, left), ("release", a, left)]
+    );
+}
+
+#[test]
+fn acquire_while_pressed_presses_each_entity_crossed() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .acquire_while_pressed(true)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let cells = [harness.spawn(), harness.spawn(), harness.spawn()];
+    harness.frame(&cells[..1], NONE);
+    assert_eq!(kinds(&harness.frame(&cells[..1], LEFT)), ["Pressed"]);
+    for (i, cell) in cells.iter().enumerate().skip(1) {
+        let transitions = harness.frame_at(Vec2::new(i as f32 * 10.0, 0.0), &[*cell], LEFT);
+        assert_eq!(
+            kinds(&transitions),
+            ["ReleasedOutside", "HoverExit", "HoverEnter", "Pressed"]
+        );
+        assert_eq!(transitions[0].entity(), cells[i - 1]);
+        assert_eq!(transitions[3].entity(), *cell);
+    }
+}
+
+#[test]
+fn bottom_left_origin_flips_the_cursor() {
+    use bevy::window::WindowResolution;
+
+    for (origin, expected) in [
+        (PointerOrigin::TopLeft, Vec2::new(100.0, 150.0)),
+        (PointerOrigin::BottomLeft, Vec2::new(100.0, 450.0)),
+    ] {
+        let plugin = PickingStateMachinePlugin::builder()
+            .pointer_origin(origin)
+            .build();
+        let mut harness = Harness::new(plugin);
+        let mut window = Window {
+            resolution: WindowResolution::new(800, 600),
+            ..Default::default()
+        };
+        window.set_cursor_position(Some(Vec2::new(100.0, 150.0)));
+        harness.app.world_mut().spawn((window, PrimaryWindow));
+        harness.frame_touch(&[], NONE);
+        assert_eq!(harness.machine().pointer, expected, "{origin:?}");
+    }
+}
+
+#[test]
+fn despawning_the_hovered_entity_reports_lost() {
+    use crate::LostReason;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.app.world_mut().despawn(a);
+    assert_eq!(
+        harness.frame(&[], NONE),
+        [PickingTransition::Lost {
+            entity: a,
+            reason: LostReason::Despawned,
+            pressed: false,
+        }]
+    );
+    assert_eq!(harness.machine().current, GlobalPickingState::None);
+}
+
+#[test]
+fn within_batch_tiebreak_picks_the_first_or_last_hit() {
+    for (tiebreak, expected) in [(HitTiebreak::FirstHit, 0), (HitTiebreak::LastHit, 1)] {
+        let plugin = PickingStateMachinePlugin::builder()
+            .within_batch_tiebreak(tiebreak)
+            .build();
+        let mut harness = Harness::new(plugin);
+        let entities = [harness.spawn(), harness.spawn()];
+        let hit = || HitData::new(harness.camera, 1.0, None, None);
+        let picks = entities.iter().map(|x| (*x, hit())).collect();
+        let hits = PointerHits::new(PointerId::Mouse, picks, 0.0);
+        harness.app.world_mut().write_message(hits);
+        harness.frame(&[], NONE);
+        assert_eq!(
+            harness.machine().current,
+            GlobalPickingState::Hover {
+                entity: entities[expected]
+            },
+            "{tiebreak:?}"
+        );
+    }
+}
+
+#[test]
+fn any_release_fires_over_empty_space() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[], NONE);
+    assert!(!harness.machine().any_release());
+    harness.frame(&[], LEFT);
+    assert!(!harness.machine().any_release());
+    assert!(harness.frame(&[], NONE).is_empty());
+    assert!(harness.machine().any_release());
+    harness.frame(&[], NONE);
+    assert!(!harness.machine().any_release());
+    // Releasing a press outside of its entity also counts.
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.frame(&[], LEFT);
+    harness.frame(&[], NONE);
+    assert!(harness.machine().any_release());
+}
+
+#[test]
+fn predicted_pointer_leads_by_the_prediction_time() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .prediction_time(0.1)
+        .build();
+    let mut harness = Harness::new(plugin);
+    // 100 pixels per second at 16ms per frame.
+    for i in 0..5 {
+        harness.frame_at(Vec2::new(i as f32 * 1.6, 0.0), &[], NONE);
+    }
+    let machine = harness.machine();
+    let lead = machine.pointer_predicted - machine.pointer;
+    assert!((lead - Vec2::new(10.0, 0.0)).length() < 1e-3, "{lead}");
+    // Without a pointer there is nothing to extrapolate.
+    harness.frame_touch(&[], NONE);
+    let machine = harness.machine();
+    assert_eq!(machine.pointer_predicted, machine.pointer);
+}
+
+#[test]
+fn clicking_a_toggle_flips_it() {
+    use crate::ToggleButton;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.app.world_mut().spawn(ToggleButton).id();
+    harness.frame(&[a], NONE);
+    for on in [true, false] {
+        harness.frame(&[a], LEFT);
+        let toggled: Vec<_> = harness
+            .frame(&[a], NONE)
+            .into_iter()
+            .filter(|x| matches!(x, PickingTransition::Toggled { .. }))
+            .collect();
+        assert_eq!(toggled, [PickingTransition::Toggled { entity: a, on }]);
+        assert_eq!(harness.machine().is_toggled(a), on);
+    }
+    // Releasing outside does not toggle.
+    harness.frame(&[a], LEFT);
+    harness.frame(&[], LEFT);
+    assert_eq!(
+        kinds(&harness.frame(&[], NONE)),
+        ["ReleasedOutside", "HoverExit"]
+    );
+    assert!(!harness.machine().is_toggled(a));
+}
+
+#[test]
+fn deepest_and_shallowest_active_of_a_subtree() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let root = harness.spawn();
+    let world = harness.app.world_mut();
+    let mid = world.spawn(ChildOf(root)).id();
+    let leaf = world.spawn(ChildOf(mid)).id();
+    let extremes = |harness: &mut Harness| {
+        harness
+            .app
+            .world_mut()
+            .run_system_once(move |propagated: PropagatedPickingStateMachine| {
+                let candidates = [leaf, root, mid];
+                (
+                    propagated.deepest_active(candidates),
+                    propagated.shallowest_active(candidates),
+                )
+            })
+            .unwrap()
+    };
+    assert_eq!(extremes(&mut harness), (None, None));
+    harness.frame(&[root], NONE);
+    assert_eq!(extremes(&mut harness), (Some(leaf), Some(root)));
+    // Ancestors of the active entity are not equivalent to it.
+    harness.frame(&[mid], NONE);
+    assert_eq!(extremes(&mut harness), (Some(leaf), Some(mid)));
+}
+
+#[test]
+fn press_commit_holds_presses_until_it_accepts() {
+    use crate::PressCommit;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    let accept = Arc::new(AtomicBool::new(false));
+    let commit = accept.clone();
+    harness
+        .app
+        .insert_resource(PressCommit::new(move |button, _| {
+            button == MouseButton::Left && commit.load(Ordering::Relaxed)
+        }));
+    harness.frame(&[a], NONE);
+    for _ in 0..3 {
+        assert!(harness.frame(&[a], LEFT).is_empty());
+        assert_eq!(
+            harness.machine().current,
+            GlobalPickingState::Hover { entity: a }
+        );
+    }
+    accept.store(true, Ordering::Relaxed);
+    assert_eq!(kinds(&harness.frame(&[a], LEFT)), ["Pressed"]);
+}
+
+#[cfg(feature = "channel")]
+#[test]
+fn collected_transitions_match_the_channel() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let channel = crate::TransitionChannel::new(16);
+    let receiver = channel.subscribe();
+    harness.app.insert_resource(channel);
+    let a = harness.spawn();
+    for (hits, buttons) in [(&[a][..], NONE), (&[a], LEFT), (&[a], NONE), (&[], NONE)] {
+        harness.frame(hits, buttons);
+        let collected = harness.machine().collect_transitions();
+        let received: Vec<_> = core::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert_eq!(collected, received);
+    }
+}
+
+#[test]
+fn prefer_current_on_tie_keeps_the_hovered_entity() {
+    for (prefer, expected) in [(true, 1), (false, 0)] {
+        let plugin = PickingStateMachinePlugin::builder()
+            .prefer_current_on_tie(prefer)
+            .build();
+        let mut harness = Harness::new(plugin);
+        let entities = [harness.spawn(), harness.spawn()];
+        harness.frame(&entities[1..], NONE);
+        for _ in 0..3 {
+            // Coplanar, so both hits have the same depth.
+            let hit = || HitData::new(harness.camera, 1.0, None, None);
+            let picks = entities.iter().map(|x| (*x, hit())).collect();
+            let hits = PointerHits::new(PointerId::Mouse, picks, 0.0);
+            harness.app.world_mut().write_message(hits);
+            harness.frame(&[], NONE);
+            assert_eq!(
+                harness.machine().current,
+                GlobalPickingState::Hover {
+                    entity: entities[expected]
+                },
+                "{prefer}"
+            );
+        }
+    }
+}
+
+#[test]
+fn press_on_recent_detects_a_quick_second_press() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .multi_click_interval(0.1)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let (a, b) = (harness.spawn(), harness.spawn());
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(harness.machine().press_on_recent(), None);
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(harness.machine().press_on_recent(), Some(a));
+    // Only the entity released last counts.
+    harness.frame(&[a], NONE);
+    harness.frame(&[b], NONE);
+    harness.frame(&[b], LEFT);
+    assert_eq!(harness.machine().press_on_recent(), None);
+    harness.frame(&[b], NONE);
+    for _ in 0..10 {
+        harness.frame(&[b], NONE);
+    }
+    harness.frame(&[b], LEFT);
+    assert_eq!(harness.machine().press_on_recent(), None);
+}
+
+#[test]
+fn pointer_over_interactive_while_pressed_elsewhere() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let (a, b) = (harness.spawn(), harness.spawn());
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    harness.frame(&[], LEFT);
+    assert!(!harness.machine().pointer_over_interactive());
+    harness.frame(&[b], LEFT);
+    let machine = harness.machine();
+    assert_eq!(machine.current, GlobalPickingState::Pressed { entity: a });
+    assert!(machine.pointer_over_interactive());
+}
+
+#[test]
+fn release_debounce_bridges_a_one_frame_release_mid_drag() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .drag_threshold(4.0)
+        .release_debounce(0.05)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert!(kinds(&harness.frame_at(Vec2::new(10.0, 0.0), &[a], LEFT)).contains(&"DragStart"));
+    assert!(harness.frame(&[a], NONE).is_empty());
+    assert!(harness.frame(&[a], LEFT).is_empty());
+    assert!(harness.machine().is_dragging());
+    assert_eq!(
+        kinds(&harness.frame_at(Vec2::new(20.0, 0.0), &[a], LEFT)),
+        ["DragMove"]
+    );
+    // Releases longer than the debounce still end the drag, reported late.
+    let mut transitions = Vec::new();
+    for _ in 0..5 {
+        transitions.extend(harness.frame(&[a], NONE));
+    }
+    assert_eq!(kinds(&transitions), ["DragEnd", "Released"]);
+}
+
+#[test]
+fn quiescent_after_idling_until_the_pointer_moves() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .skip_when_quiescent(true)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let (a, b) = (harness.spawn(), harness.spawn());
+    assert_eq!(
+        kinds(&harness.frame_at(Vec2::new(1.0, 0.0), &[a], NONE)),
+        ["HoverEnter"]
+    );
+    assert!(!harness.machine().is_quiescent());
+    harness.frame(&[a], NONE);
+    assert!(harness.machine().is_quiescent());
+    // Movement and presses are caught the frame they occur.
+    assert_eq!(
+        kinds(&harness.frame_at(Vec2::new(5.0, 0.0), &[b], NONE)),
+        ["HoverExit", "HoverEnter"]
+    );
+    assert!(!harness.machine().is_quiescent());
+    harness.frame(&[b], NONE);
+    assert!(harness.machine().is_quiescent());
+    assert_eq!(kinds(&harness.frame(&[b], LEFT)), ["Pressed"]);
+    assert!(!harness.machine().is_quiescent());
+}
+
+#[test]
+fn rehover_on_release_emits_hover_enter() {
+    for (rehover, expected) in [
+        (false, &["Released", "Clicked"][..]),
+        (true, &["Released", "HoverEnter", "Clicked"]),
+    ] {
+        let plugin = PickingStateMachinePlugin::builder()
+            .rehover_on_release(rehover)
+            .build();
+        let mut harness = Harness::new(plugin);
+        let a = harness.spawn();
+        harness.frame(&[a], NONE);
+        harness.frame(&[a], LEFT);
+        assert_eq!(kinds(&harness.frame(&[a], NONE)), expected, "{rehover}");
+    }
+}
+
+#[test]
+fn interaction_kind_of_each_interaction() {
+    use crate::InteractionKind;
+
+    let plugin = PickingStateMachinePlugin::builder()
+        .drag_threshold(4.0)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let a = harness.spawn();
+    let mut kind = |hits: &[Entity], buttons: &[MouseButton], x: f32| {
+        harness.frame_at(Vec2::new(x, 0.0), hits, buttons);
+        harness.machine().interaction_kind()
+    };
+    assert_eq!(kind(&[], NONE, 0.0), InteractionKind::Idle);
+    assert_eq!(kind(&[a], NONE, 0.0), InteractionKind::Hovering(a));
+    assert_eq!(kind(&[a], LEFT, 0.0), InteractionKind::Pressing(a));
+    assert_eq!(kind(&[a], LEFT, 10.0), InteractionKind::Dragging(a));
+    assert_eq!(kind(&[a], NONE, 10.0), InteractionKind::Hovering(a));
+    assert_eq!(kind(&[], NONE, 10.0), InteractionKind::Idle);
+}
+
+#[test]
+fn snapped_drag_offset_lands_on_the_grid() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    let grid = Vec2::new(10.0, 10.0);
+    let origin = Vec2::new(2.0, 0.0);
+    harness.frame(&[a], NONE);
+    assert_eq!(harness.machine().snapped_drag_offset(grid, origin), None);
+    harness.frame(&[a], LEFT);
+    // Many small moves snap like the total offset.
+    for i in 1..=9 {
+        harness.frame_at(Vec2::new(i as f32 * 3.0, i as f32 * -0.8), &[a], LEFT);
+    }
+    let machine = harness.machine();
+    assert_eq!(
+        machine.snapped_drag_offset(grid, origin),
+        Some(Vec2::new(28.0, -10.0))
+    );
+    assert_eq!(
+        machine.snapped_drag_offset(Vec2::new(0.0, 10.0), origin),
+        Some(Vec2::new(27.0, -10.0))
+    );
+}
+
+#[test]
+fn press_acquire_on_spawn_presses_an_entity_spawned_under_a_held_button() {
+    for (acquire, expected) in [(false, &[][..]), (true, &["Pressed"])] {
+        let plugin = PickingStateMachinePlugin::builder()
+            .press_acquire_on_spawn(acquire)
+            .build();
+        let mut harness = Harness::new(plugin);
+        harness.frame(&[], NONE);
+        harness.frame(&[], LEFT);
+        let a = harness.spawn();
+        assert_eq!(kinds(&harness.frame(&[a], LEFT)), expected, "{acquire}");
+        // Entities that existed before are never pressed by a held button.
+        let b = harness.spawn();
+        harness.frame(&[], NONE);
+        harness.frame(&[], LEFT);
+        assert!(harness.frame(&[b], LEFT).is_empty(), "{acquire}");
+    }
+}
+
+#[test]
+fn current_camera_is_the_camera_of_the_winning_hit() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let (a, b) = (harness.spawn(), harness.spawn());
+    let cameras = [harness.spawn(), harness.spawn()];
+    let mut frame = |top: usize| {
+        // The batch with the higher order is on top.
+        for (i, (entity, camera)) in [a, b].into_iter().zip(cameras).enumerate() {
+            let hit = HitData::new(camera, 0.0, None, None);
+            let order = if i == top { 1.0 } else { 0.0 };
+            let hits = PointerHits::new(PointerId::Mouse, vec![(entity, hit)], order);
+            harness.app.world_mut().write_message(hits);
+        }
+        harness.frame(&[], NONE);
+        let machine = harness.machine();
+        (machine.get_active_entity(), machine.current_camera)
+    };
+    assert_eq!(frame(0), (Some(a), Some(cameras[0])));
+    assert_eq!(frame(1), (Some(b), Some(cameras[1])));
+}
+
+#[cfg(feature = "debug")]
+#[test]
+fn rapid_hover_flicker_increments_the_diagnostic() {
+    use crate::FLICKER_THRESHOLD;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let (a, b) = (harness.spawn(), harness.spawn());
+    for _ in 0..FLICKER_THRESHOLD - 1 {
+        harness.frame(&[a], NONE);
+        harness.frame(&[], NONE);
+    }
+    assert_eq!(harness.machine().flicker_count, 0);
+    harness.frame(&[a], NONE);
+    assert_eq!(harness.machine().flicker_count, 1);
+    // Only re-entering the same entity counts.
+    for _ in 0..FLICKER_THRESHOLD {
+        harness.frame(&[b], NONE);
+        harness.frame(&[a], NONE);
+    }
+    assert_eq!(harness.machine().flicker_count, 1);
+    // Slow re-hovers never flicker.
+    for _ in 0..FLICKER_THRESHOLD * 2 {
+        harness.frame(&[], NONE);
+        for _ in 0..20 {
+            harness.frame(&[a], NONE);
+        }
+    }
+    assert_eq!(harness.machine().flicker_count, 1);
+}
+
+#[test]
+fn max_hit_batches_reads_only_the_first_batches() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .max_hit_batches(Some(1))
+        .build();
+    let mut harness = Harness::new(plugin);
+    let (a, b) = (harness.spawn(), harness.spawn());
+    // `b` would be on top if its batch was read.
+    for (entity, order) in [(a, 0.0), (b, 1.0)] {
+        let hit = HitData::new(harness.camera, 0.0, None, None);
+        let hits = PointerHits::new(PointerId::Mouse, vec![(entity, hit)], order);
+        harness.app.world_mut().write_message(hits);
+    }
+    assert_eq!(
+        harness.frame(&[], NONE),
+        [PickingTransition::HoverEnter { entity: a }]
+    );
+    // The ignored batch is drained instead of read on the next frame.
+    assert_eq!(
+        harness.frame(&[], NONE),
+        [PickingTransition::HoverExit { entity: a }]
+    );
+}
+
+#[test]
+fn run_conditions_follow_the_state() {
+    use crate::conditions::{any_active, is_hovering, is_pressing, transition_occurred};
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    let mut conditions = |hits: &[Entity], buttons: &[MouseButton]| {
+        harness.frame(hits, buttons);
+        let world = harness.app.world_mut();
+        [
+            world.run_system_once(any_active).unwrap(),
+            world.run_system_once(is_hovering).unwrap(),
+            world.run_system_once(is_pressing).unwrap(),
+            world.run_system_once(transition_occurred).unwrap(),
+        ]
+    };
+    assert_eq!(conditions(&[], NONE), [false, false, false, false]);
+    assert_eq!(conditions(&[a], NONE), [true, true, false, true]);
+    assert_eq!(conditions(&[a], NONE), [true, true, false, false]);
+    assert_eq!(conditions(&[a], LEFT), [true, false, true, true]);
+    assert_eq!(conditions(&[a], LEFT), [true, false, true, false]);
+    assert_eq!(conditions(&[], NONE), [false, false, false, true]);
+}
+
+#[cfg(feature = "reflect")]
+#[test]
+fn state_machine_is_registered_for_reflection() {
+    use bevy::{
+        ecs::reflect::{AppTypeRegistry, ReflectResource},
+        reflect::{GetPath, Reflect},
+    };
+    use core::any::TypeId;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    let registry = harness.app.world().resource::<AppTypeRegistry>().read();
+    let machine = TypeId::of::<PickingStateMachine>();
+    assert!(registry.get_type_data::<ReflectResource>(machine).is_some());
+    assert!(registry.contains(TypeId::of::<PickingTransition>()));
+    let machine: &dyn Reflect = harness.machine();
+    let current = machine.reflect_path("current").unwrap();
+    assert_eq!(
+        current.try_downcast_ref::<GlobalPickingState>(),
+        Some(&GlobalPickingState::Hover { entity: a })
+    );
+}
+
+#[test]
+fn pick_roots_keep_sibling_panels_independent() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let parent = harness.spawn();
+    let world = harness.app.world_mut();
+    let panel_a = world.spawn((ChildOf(parent), PickRoot)).id();
+    let label_a = world.spawn(ChildOf(panel_a)).id();
+    let panel_b = world.spawn((ChildOf(parent), PickRoot)).id();
+    let label_b = world.spawn(ChildOf(panel_b)).id();
+    // Without the roots this would reach every descendant of `parent`.
+    let button_b = world
+        .spawn((ChildOf(panel_b), PickingPropagation::PropagateUp(2)))
+        .id();
+    harness.frame(&[button_b], NONE);
+    let states = harness
+        .app
+        .world_mut()
+        .run_system_once(move |propagated: PropagatedPickingStateMachine| {
+            [parent, panel_a, label_a, label_b, button_b].map(|x| propagated.get_state(x))
+        })
+        .unwrap();
+    let (none, hover) = (EntityPickingState::None, EntityPickingState::Hover);
+    assert_eq!(states, [none, none, none, hover, hover]);
+}
+
+#[test]
+fn activated_respects_filters_modes_and_drags() {
+    use crate::{ActivationMode, ButtonFilter};
+
+    let plugin = PickingStateMachinePlugin::builder()
+        .drag_threshold(4.0)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let release = harness.spawn();
+    let world = harness.app.world_mut();
+    let press = world.spawn(ActivationMode::Press).id();
+    let double = world.spawn(ActivationMode::DoubleClick).id();
+    // Pressed through propagation from `release`, but only allows the right button.
+    let denied = world
+        .spawn((ChildOf(release), ButtonFilter::new([MouseButton::Right])))
+        .id();
+    let mut frame = |hit: Entity, buttons: &[MouseButton], x: f32, target: Entity| {
+        harness.frame_at(Vec2::new(x, 0.0), &[hit], buttons);
+        harness
+            .app
+            .world_mut()
+            .run_system_once(
+                move |propagated: PropagatedPickingStateMachine,
+                      filters: Query<&ButtonFilter>,
+                      modes: Query<&ActivationMode>| {
+                    propagated.activated(target, &filters, &modes)
+                },
+            )
+            .unwrap()
+    };
+    let left = Some(MouseButton::Left);
+    let mut click = |entity: Entity, target: Entity| {
+        [(NONE, 0.0), (LEFT, 0.0), (NONE, 0.0)]
+            .map(|(buttons, x)| frame(entity, buttons, x, target))
+    };
+    assert_eq!(click(release, release), [None, None, left]);
+    assert_eq!(click(release, denied), [None, None, None]);
+    assert_eq!(click(press, press), [None, left, None]);
+    assert_eq!(click(double, double), [None, None, None]);
+    assert_eq!(click(double, double), [None, None, left]);
+    // Drags never activate on release.
+    let dragged = [(NONE, 0.0), (LEFT, 0.0), (LEFT, 10.0), (NONE, 10.0)]
+        .map(|(buttons, x)| frame(release, buttons, x, release));
+    assert_eq!(dragged, [None; 4]);
+}
+
+#[test]
+fn modifiers_are_captured_at_press_time() {
+    use crate::Modifiers;
+    use bevy::input::{
+        ButtonState,
+        keyboard::{Key, KeyCode, KeyboardInput},
+    };
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let key = |harness: &mut Harness, key_code, logical_key, state| {
+        harness.app.world_mut().write_message(KeyboardInput {
+            key_code,
+            logical_key,
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    };
+    let a = harness.spawn();
+    harness.frame(&[a], NONE);
+    key(
+        &mut harness,
+        KeyCode::ShiftLeft,
+        Key::Shift,
+        ButtonState::Pressed,
+    );
+    let pressed = harness.frame(&[a], LEFT);
+    let shift = Modifiers::SHIFT;
+    assert!(
+        matches!(pressed[..], [PickingTransition::Pressed { modifiers, .. }] if modifiers == shift)
+    );
+    assert!(harness.machine().press.unwrap().modifiers.shift());
+    key(
+        &mut harness,
+        KeyCode::ShiftLeft,
+        Key::Shift,
+        ButtonState::Released,
+    );
+    key(
+        &mut harness,
+        KeyCode::ControlRight,
+        Key::Control,
+        ButtonState::Pressed,
+    );
+    harness.frame(&[a], LEFT);
+    let released = harness.frame(&[a], NONE);
+    assert!(
+        matches!(released[0], PickingTransition::Released { modifiers, .. } if modifiers == shift)
+    );
+    // The next press sees the modifiers held then.
+    harness.frame(&[a], LEFT);
+    let modifiers = harness.machine().press.unwrap().modifiers;
+    assert!(modifiers.ctrl() && !modifiers.shift());
+}
+
+#[test]
+fn tracked_button_edges_across_press_hold_release() {
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let a = harness.spawn();
+    let mut edges = |hits: &[Entity], buttons: &[MouseButton]| {
+        harness.frame(hits, buttons);
+        let machine = harness.machine();
+        (
+            machine.tracked_button(),
+            machine.tracked_just_pressed(),
+            machine.tracked_just_released(),
+        )
+    };
+    let left = Some(MouseButton::Left);
+    assert_eq!(edges(&[a], NONE), (None, false, false));
+    assert_eq!(edges(&[a], LEFT), (left, true, false));
+    assert_eq!(edges(&[a], LEFT), (left, false, false));
+    assert!(edges(&[a], NONE).2);
+    assert_eq!(edges(&[a], NONE), (None, false, false));
+    // Presses over nothing are tracked too.
+    assert_eq!(edges(&[], LEFT), (left, true, false));
+    assert!(edges(&[], NONE).2);
+}
+
+#[test]
+fn higher_pick_tier_blocks_the_world_behind_it() {
+    use crate::PickTier;
+
+    let mut harness = Harness::new(PickingStateMachinePlugin::default());
+    let world_entity = harness.spawn();
+    let hud = harness.app.world_mut().spawn(PickTier(1)).id();
+    // The world is on top by order, only the tier puts the HUD first.
+    let mut frame = |hits: &[Entity], buttons: &[MouseButton]| {
+        for entity in hits {
+            let order = if *entity == hud { 0.0 } else { 1.0 };
+            let hit = HitData::new(harness.camera, 0.0, None, None);
+            let hits = PointerHits::new(PointerId::Mouse, vec![(*entity, hit)], order);
+            harness.app.world_mut().write_message(hits);
+        }
+        harness.frame(&[], buttons);
+        harness.machine().current
+    };
+    assert_eq!(
+        frame(&[world_entity], NONE),
+        GlobalPickingState::Hover {
+            entity: world_entity
+        }
+    );
+    assert_eq!(
+        frame(&[world_entity, hud], NONE),
+        GlobalPickingState::Hover { entity: hud }
+    );
+    assert_eq!(
+        frame(&[world_entity, hud], LEFT),
+        GlobalPickingState::Pressed { entity: hud }
+    );
+}
+
+#[test]
+fn last_click_persists_until_the_next_click() {
+    let plugin = PickingStateMachinePlugin::builder()
+        .allowed_button(MouseButton::Right)
+        .build();
+    let mut harness = Harness::new(plugin);
+    let (a, b) = (harness.spawn(), harness.spawn());
+    let at = Vec2::new(5.0, 5.0);
+    harness.frame_at(at, &[a], NONE);
+    harness.frame(&[a], LEFT);
+    assert_eq!(harness.machine().last_click(), None);
+    harness.frame(&[a], NONE);
+    for _ in 0..3 {
+        assert_eq!(
+            harness.machine().last_click(),
+            Some((a, at, MouseButton::Left))
+        );
+        harness.frame(&[], NONE);
+    }
+    // Releasing outside is not a click.
+    harness.frame(&[a], LEFT);
+    harness.frame(&[], LEFT);
+    harness.frame(&[], NONE);
+    assert_eq!(
+        harness.machine().last_click(),
+        Some((a, at, MouseButton::Left))
+    );
+    let at = Vec2::new(20.0, 0.0);
+    harness.frame_at(at, &[b], NONE);
+    harness.frame(&[b], &[MouseButton::Right]);
+    harness.frame(&[b], NONE);
+    assert_eq!(
+        harness.machine().last_click(),
+        Some((b, at, MouseButton::Right))
+    );
+}
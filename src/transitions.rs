@@ -1,19 +1,49 @@
-use crate::{GlobalPickingState, PickingStateMachine};
-use bevy::{ecs::entity::Entity, input::mouse::MouseButton, math::Vec2};
+use crate::{
+    GlobalPickingState, Modifiers, PickingStateMachine, PickingStateMachinePlugin, PointerSource,
+};
+#[cfg(feature = "reflect")]
+use bevy::reflect::Reflect;
+use bevy::{
+    ecs::{entity::Entity, message::Message},
+    input::mouse::MouseButton,
+    math::Vec2,
+    picking::backend::HitData,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
 
 /// A picking transition event.
-#[derive(Debug, Clone, Copy)]
+///
+/// Also written as a [`Message`] every frame, and triggered as an
+/// [`EntityEvent`](bevy::ecs::event::EntityEvent) on its entity
+/// if `PickingStateMachinePlugin::emit_events` is set.
+///
+/// Equality compares `f32` fields like `time` exactly,
+/// use [`PickingTransition::same_kind`] to ignore the payload.
+#[derive(Debug, Clone, PartialEq, Message)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PickingTransition {
     Pressed {
         entity: Entity,
         button: MouseButton,
+        source: PointerSource,
+        /// Keyboard modifiers held when the press started.
+        modifiers: Modifiers,
     },
     Released {
         entity: Entity,
         button: MouseButton,
         down: Vec2,
+        /// Seconds the press was held, measured by `PickingStateMachine::now`.
         time: f32,
         outside: bool,
+        source: PointerSource,
+        /// Keyboard modifiers held when the press started, not at release.
+        modifiers: Modifiers,
+        /// Distance in pixels the pointer traveled during the press.
+        travel: f32,
     },
     HoverEnter {
         entity: Entity,
@@ -25,11 +55,190 @@ pub enum PickingTransition {
         entity: Entity,
         button: MouseButton,
         down: Vec2,
+        /// Seconds the press was held, measured by `PickingStateMachine::now`.
         time: f32,
+        /// Buttons held when the cancellation was detected, the tracked button first.
+        ///
+        /// Empty if not cancelled by multiple buttons, e.g. by `PickingStateMachine::request_cancel`.
+        chord: SmallVec<[MouseButton; 2]>,
+        source: PointerSource,
+        /// Distance in pixels the pointer traveled during the press.
+        travel: f32,
+    },
+    /// A [`ToggleButton`](crate::ToggleButton) is clicked.
+    Toggled {
+        entity: Entity,
+        on: bool,
+    },
+    /// Pressed and released inside the same entity without cancellation or dragging.
+    Clicked {
+        entity: Entity,
+        button: MouseButton,
+        /// Number of consecutive clicks, 2 for a double click.
+        count: u32,
+    },
+    /// A press is held for `PickingStateMachinePlugin::long_press_duration`
+    /// without becoming a drag, emitted once per press.
+    LongPress {
+        entity: Entity,
+        button: MouseButton,
+        /// Position of the press.
+        position: Vec2,
+    },
+    /// A press moved further than `PickingStateMachinePlugin::drag_threshold`.
+    DragStart {
+        entity: Entity,
+    },
+    /// The pointer moved while dragging.
+    DragMove {
+        entity: Entity,
+        /// Pointer movement since the last drag transition.
+        delta: Vec2,
+    },
+    /// A drag ended, emitted before the release or cancellation of the press.
+    DragEnd {
+        entity: Entity,
+    },
+    /// The pointer left the pressed entity with `PressRetention::ReleaseWhileOutside`.
+    DragOutside {
+        entity: Entity,
+    },
+    /// The pointer returned to the pressed entity with `PressRetention::ReleaseWhileOutside`.
+    DragReturn {
+        entity: Entity,
+    },
+    /// The tracked button of the press on `entity` changed without a release,
+    /// under [`MultiButtonPolicy::FirstWins`](crate::MultiButtonPolicy::FirstWins).
+    ///
+    /// Later transitions of the press carry `to` as their button.
+    TrackingSwitched {
+        entity: Entity,
+        from: MouseButton,
+        to: MouseButton,
+    },
+    /// The topmost entity under the pointer changed while dragging,
+    /// see `PickingStateMachine::drag_over`.
+    DragEnter {
+        entity: Entity,
+        /// True if `entity` is the dragged entity itself, i.e. not a drop candidate.
+        is_source: bool,
+    },
+    /// The pointer stayed on a [`PickingTransition::DragEnter`] target, other than the dragged entity,
+    /// for `PickingStateMachinePlugin::drop_dwell` while dragging, emitted once per target.
+    DropTargetArmed {
+        entity: Entity,
+    },
+    /// A [`Selectable`](crate::Selectable) is clicked and becomes
+    /// the selected entity of [`SelectionState`](crate::SelectionState).
+    Selected {
+        entity: Entity,
+        previous: Option<Entity>,
+    },
+    /// The selection of [`SelectionState`](crate::SelectionState) is cleared by clicking empty space.
+    Deselected {
+        entity: Entity,
+    },
+    /// Hit data on the pressed entity changed,
+    /// only emitted if `PickingStateMachinePlugin::emit_hit_updates` is set.
+    HitUpdate {
+        entity: Entity,
+        /// Serialized without [`HitData::extra`], which is backend specific.
+        #[cfg_attr(feature = "serde", serde(with = "hit_data"))]
+        hit: HitData,
+    },
+    /// The mouse wheel scrolled over the hovered or pressed entity,
+    /// only emitted if `PickingStateMachinePlugin::route_scroll` is set.
+    Scrolled {
+        entity: Entity,
+        /// Sum of the frame's wheel movements in pixels.
+        delta: Vec2,
+    },
+    /// The active entity lost its role without a normal exit.
+    ///
+    /// Preceded by [`PickingTransition::Cancelled`] if the entity was pressed.
+    Lost {
+        entity: Entity,
+        reason: LostReason,
+        /// True if the entity was pressed.
+        pressed: bool,
     },
 }
 
+/// Serializes [`HitData`] without its `extra` data.
+#[cfg(feature = "serde")]
+mod hit_data {
+    use bevy::{ecs::entity::Entity, math::Vec3, picking::backend::HitData};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Hit {
+        camera: Entity,
+        depth: f32,
+        position: Option<Vec3>,
+        normal: Option<Vec3>,
+    }
+
+    pub fn serialize<S: Serializer>(hit: &HitData, serializer: S) -> Result<S::Ok, S::Error> {
+        Hit {
+            camera: hit.camera,
+            depth: hit.depth,
+            position: hit.position,
+            normal: hit.normal,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HitData, D::Error> {
+        let hit = Hit::deserialize(deserializer)?;
+        Ok(HitData::new(
+            hit.camera,
+            hit.depth,
+            hit.position,
+            hit.normal,
+        ))
+    }
+}
+
+/// Reason for [`PickingTransition::Lost`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum LostReason {
+    /// The active entity was despawned.
+    Despawned,
+}
+
+/// How a press ended, see `PickingStateMachine::press_ended`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PressEnd {
+    pub entity: Entity,
+    pub reason: EndReason,
+}
+
+/// Reason of a [`PressEnd`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "reflect", derive(Reflect))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EndReason {
+    /// Released over the pressed entity.
+    ReleasedInside,
+    /// Released outside of the pressed entity.
+    ReleasedOutside,
+    /// Cancelled by pressing multiple buttons.
+    Cancelled,
+    /// The pressed entity is lost, e.g. despawned.
+    LostTarget,
+}
+
 impl PickingTransition {
+    /// Returns true if both transitions are the same variant, regardless of their fields.
+    pub fn same_kind(&self, other: &PickingTransition) -> bool {
+        core::mem::discriminant(self) == core::mem::discriminant(other)
+    }
+
     pub fn entity(&self) -> Entity {
         match *self {
             PickingTransition::Pressed { entity, .. } => entity,
@@ -37,26 +246,136 @@ impl PickingTransition {
             PickingTransition::HoverEnter { entity } => entity,
             PickingTransition::HoverExit { entity } => entity,
             PickingTransition::Cancelled { entity, .. } => entity,
+            PickingTransition::Toggled { entity, .. } => entity,
+            PickingTransition::Clicked { entity, .. } => entity,
+            PickingTransition::LongPress { entity, .. } => entity,
+            PickingTransition::DragStart { entity } => entity,
+            PickingTransition::DragMove { entity, .. } => entity,
+            PickingTransition::DragEnd { entity } => entity,
+            PickingTransition::DragOutside { entity } => entity,
+            PickingTransition::DragReturn { entity } => entity,
+            PickingTransition::DragEnter { entity, .. } => entity,
+            PickingTransition::TrackingSwitched { entity, .. } => entity,
+            PickingTransition::DropTargetArmed { entity } => entity,
+            PickingTransition::Selected { entity, .. } => entity,
+            PickingTransition::Deselected { entity } => entity,
+            PickingTransition::HitUpdate { entity, .. } => entity,
+            PickingTransition::Scrolled { entity, .. } => entity,
+            PickingTransition::Lost { entity, .. } => entity,
+        }
+    }
+
+    /// Returns the button of the transition, `None` for transitions not tied to a button.
+    pub fn button(&self) -> Option<MouseButton> {
+        match *self {
+            PickingTransition::Pressed { button, .. } => Some(button),
+            PickingTransition::Released { button, .. } => Some(button),
+            PickingTransition::Cancelled { button, .. } => Some(button),
+            PickingTransition::Clicked { button, .. } => Some(button),
+            PickingTransition::LongPress { button, .. } => Some(button),
+            PickingTransition::TrackingSwitched { to, .. } => Some(to),
+            PickingTransition::HoverEnter { .. }
+            | PickingTransition::HoverExit { .. }
+            | PickingTransition::Toggled { .. }
+            | PickingTransition::DragStart { .. }
+            | PickingTransition::DragMove { .. }
+            | PickingTransition::DragEnd { .. }
+            | PickingTransition::DragOutside { .. }
+            | PickingTransition::DragReturn { .. }
+            | PickingTransition::DragEnter { .. }
+            | PickingTransition::DropTargetArmed { .. }
+            | PickingTransition::Selected { .. }
+            | PickingTransition::Deselected { .. }
+            | PickingTransition::HitUpdate { .. }
+            | PickingTransition::Scrolled { .. }
+            | PickingTransition::Lost { .. } => None,
+        }
+    }
+
+    /// Returns true for a [`PickingTransition::Released`] inside its entity.
+    ///
+    /// Unlike [`PickingTransition::Clicked`], this includes releases ending a drag.
+    pub fn is_click(&self) -> bool {
+        matches!(self, PickingTransition::Released { outside: false, .. })
+    }
+
+    /// Returns the entity and button of a [`PickingTransition::Released`] inside its entity.
+    pub fn released_inside(&self) -> Option<(Entity, MouseButton)> {
+        match *self {
+            PickingTransition::Released {
+                entity,
+                button,
+                outside: false,
+                ..
+            } => Some((entity, button)),
+            _ => None,
         }
     }
 }
 
 impl PickingStateMachine {
-    pub(crate) fn queue_transitions(&mut self, now: f32) {
+    /// Insert transitions for the despawned active entity at the front,
+    /// `Cancelled` if it was pressed, then `Lost`.
+    pub(crate) fn insert_lost(&mut self, entity: Entity, pressed: bool, now: f32) {
+        self.transitions.insert(
+            0,
+            PickingTransition::Lost {
+                entity,
+                reason: LostReason::Despawned,
+                pressed,
+            },
+        );
+        if let Some(press) = self.press.filter(|_| pressed) {
+            self.transitions.insert(
+                0,
+                PickingTransition::Cancelled {
+                    entity,
+                    button: press.button,
+                    down: press.position,
+                    time: now - press.time,
+                    chord: self.chord.clone(),
+                    source: press.source,
+                    travel: self.press_travel,
+                },
+            );
+        }
+    }
+
+    pub(crate) fn queue_transitions(&mut self, now: f32, settings: &PickingStateMachinePlugin) {
         use GlobalPickingState::*;
         self.transitions.clear();
         let time = self.press.map(|x| now - x.time).unwrap_or(0.0);
         let button = self.press.map(|x| x.button).unwrap_or(MouseButton::Left);
         let down = self.press.map(|x| x.position).unwrap_or(Vec2::ZERO);
-        match (self.previous, self.current) {
+        let source = self.press.map(|x| x.source).unwrap_or_default();
+        let modifiers = self.press.map(|x| x.modifiers).unwrap_or_default();
+        let travel = self.press_travel;
+        let dragged = |state: GlobalPickingState| match state {
+            Dragging { entity } => Some(entity),
+            _ => Option::None,
+        };
+        // Drags are presses for the purpose of press transitions.
+        let pressed = |state: GlobalPickingState| match state {
+            Dragging { entity } => Pressed { entity },
+            state => state,
+        };
+        let (was_dragging, dragging) = (dragged(self.previous), dragged(self.current));
+        if let Some(entity) = was_dragging.filter(|e| dragging != Some(*e)) {
+            self.transitions.push(PickingTransition::DragEnd { entity });
+        }
+        match (pressed(self.previous), pressed(self.current)) {
             (None, None) => (),
             (None, Hover { entity }) => {
                 self.transitions
                     .push(PickingTransition::HoverEnter { entity });
             }
             (None, Pressed { entity }) => {
-                self.transitions
-                    .push(PickingTransition::Pressed { entity, button });
+                self.transitions.push(PickingTransition::Pressed {
+                    entity,
+                    button,
+                    source,
+                    modifiers,
+                });
             }
             (Hover { entity }, None) => {
                 self.transitions
@@ -72,15 +391,23 @@ impl PickingStateMachine {
             }
             (Hover { entity: e1 }, Pressed { entity: e2 }) => {
                 if e1 == e2 {
-                    self.transitions
-                        .push(PickingTransition::Pressed { entity: e1, button });
+                    self.transitions.push(PickingTransition::Pressed {
+                        entity: e1,
+                        button,
+                        source,
+                        modifiers,
+                    });
                 } else {
                     self.transitions
                         .push(PickingTransition::HoverExit { entity: e1 });
                     self.transitions
                         .push(PickingTransition::HoverEnter { entity: e2 });
-                    self.transitions
-                        .push(PickingTransition::Pressed { entity: e2, button });
+                    self.transitions.push(PickingTransition::Pressed {
+                        entity: e2,
+                        button,
+                        source,
+                        modifiers,
+                    });
                 }
             }
             (Pressed { entity }, None) => {
@@ -90,6 +417,9 @@ impl PickingStateMachine {
                         down,
                         time,
                         button,
+                        chord: self.chord.clone(),
+                        source,
+                        travel,
                     });
                 } else {
                     self.transitions.push(PickingTransition::Released {
@@ -98,6 +428,9 @@ impl PickingStateMachine {
                         down,
                         time,
                         outside: true,
+                        source,
+                        travel,
+                        modifiers,
                     });
                     self.transitions
                         .push(PickingTransition::HoverExit { entity });
@@ -111,7 +444,14 @@ impl PickingStateMachine {
                         down,
                         time,
                         outside: false,
+                        source,
+                        travel,
+                        modifiers,
                     });
+                    if settings.rehover_on_release {
+                        self.transitions
+                            .push(PickingTransition::HoverEnter { entity: e1 });
+                    }
                 } else {
                     self.transitions.push(PickingTransition::Released {
                         entity: e1,
@@ -119,6 +459,9 @@ impl PickingStateMachine {
                         down,
                         time,
                         outside: true,
+                        source,
+                        travel,
+                        modifiers,
                     });
                     self.transitions
                         .push(PickingTransition::HoverExit { entity: e1 });
@@ -135,15 +478,58 @@ impl PickingStateMachine {
                         down,
                         time,
                         outside: true,
+                        source,
+                        travel,
+                        modifiers,
                     });
                     self.transitions
                         .push(PickingTransition::HoverExit { entity: e1 });
                     self.transitions
                         .push(PickingTransition::HoverEnter { entity: e2 });
-                    self.transitions
-                        .push(PickingTransition::Pressed { entity: e2, button });
+                    self.transitions.push(PickingTransition::Pressed {
+                        entity: e2,
+                        button,
+                        source,
+                        modifiers,
+                    });
                 }
             }
+            (_, Dragging { .. }) | (Dragging { .. }, _) => unreachable!(),
+        }
+        if self
+            .transitions
+            .iter()
+            .any(|x| matches!(x, PickingTransition::Pressed { .. }))
+        {
+            self.pressed_since = now;
+        }
+        self.hover_started = match self.current {
+            Hover { .. } if self.previous == self.current => self.hover_started.or(Some(now)),
+            Hover { .. } => Some(now),
+            _ => Option::None,
+        };
+        match dragging {
+            Some(entity) if was_dragging != Some(entity) => {
+                self.transitions
+                    .push(PickingTransition::DragStart { entity });
+                self.drag_pointer = self.pointer;
+            }
+            Some(entity) if self.pointer != self.drag_pointer => {
+                self.transitions.push(PickingTransition::DragMove {
+                    entity,
+                    delta: self.pointer - self.drag_pointer,
+                });
+                self.drag_pointer = self.pointer;
+            }
+            _ => (),
         }
+        debug_assert!(
+            self.transitions
+                .iter()
+                .enumerate()
+                .all(|(i, a)| self.transitions[..i].iter().all(|b| !a.same_kind(b))),
+            "At most one transition of each kind per frame, found {:?}.",
+            self.transitions
+        );
     }
 }